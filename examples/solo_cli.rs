@@ -1,25 +1,25 @@
 use std::io;
+use twentyone::cards::{Card, Suit};
 use twentyone::game::get_hand_value;
 use twentyone::prelude::*;
 
 /// Turn a card into a 2-character string
-fn card_to_printable(card: &[char; 2]) -> String {
+fn card_to_printable(card: &Card) -> String {
     format!(
         "{}{}",
         // Suit
-        match card[0] {
-            'S' => '♠',
-            'H' => '♥',
-            'C' => '♣',
-            'D' => '♦',
-            _ => ' ',
+        match card.suit {
+            Suit::Spades => '♠',
+            Suit::Hearts => '♥',
+            Suit::Clubs => '♣',
+            Suit::Diamonds => '♦',
         },
         // Value
-        card[1]
+        card.rank
     )
 }
 
-fn callback(request: DealerRequest, p: Option<&Player>, dealer: &Dealer) -> PlayerAction {
+fn callback(request: DealerRequest, p: Option<&Player>, _: &Dealer) -> PlayerAction {
     match request {
         DealerRequest::Bet => {
             println!("Current Balance: {}", p.unwrap().money());
@@ -43,7 +43,7 @@ fn callback(request: DealerRequest, p: Option<&Player>, dealer: &Dealer) -> Play
                 get_hand_value(&p.unwrap().hands()[i], true)
             );
             // Request action from user
-            println!("Enter one of [H]it, [S]tand, [D]ouble Down, S[p]lit");
+            println!("Enter one of [H]it, [S]tand, [D]ouble Down, S[p]lit, Su[r]render");
             // Read line for action
             let mut input = String::new();
             io::stdin().read_line(&mut input).expect("");
@@ -55,9 +55,19 @@ fn callback(request: DealerRequest, p: Option<&Player>, dealer: &Dealer) -> Play
                 "s" | "stand" => PlayerAction::Stand,
                 "d" | "double" | "double down" => PlayerAction::DoubleDown,
                 "p" | "split" => PlayerAction::Split,
+                "r" | "surrender" => PlayerAction::Surrender,
                 _ => PlayerAction::None,
             }
         }
+        DealerRequest::Insurance => {
+            println!("Insurance (0 to decline): ");
+            // Read line for insurance amount
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).expect("");
+            // Strip whitespace
+            input.retain(|c| !c.is_whitespace());
+            PlayerAction::Insurance(input.parse::<i32>().unwrap_or(0))
+        }
         DealerRequest::UpCard(card) => {
             println!("Dealer up card: {}", card_to_printable(&card));
             PlayerAction::None
@@ -67,7 +77,6 @@ fn callback(request: DealerRequest, p: Option<&Player>, dealer: &Dealer) -> Play
             PlayerAction::None
         }
         DealerRequest::DealerHand(h) => {
-            let dealer_value = get_hand_value(&h, true);
             // Print dealer hand
             println!("Dealer hand:");
             for card in h.iter() {
@@ -75,36 +84,13 @@ fn callback(request: DealerRequest, p: Option<&Player>, dealer: &Dealer) -> Play
             }
             // Print hand value
             println!(" Total value: {}\n", get_hand_value(&h, true));
-            // Print results of player hands
-            for i in 0..dealer.players()[0].hands().len() {
-                let player_value = get_hand_value(&dealer.players()[0].hands()[i], true);
-                // Print hand
-                println!("Player Hand {}:", i + 1);
-                for card in dealer.players()[0].hands()[i].iter() {
-                    print!("|{}|", card_to_printable(card));
-                }
-                // Print hand value
-                println!(
-                    " Total value: {}",
-                    get_hand_value(&dealer.players()[0].hands()[i], true)
-                );
-                if player_value > dealer_value && player_value <= 21
-                    || (player_value <= 21 && dealer_value > 21)
-                {
-                    println!("Result Hand {}: Win!", i + 1);
-                } else if player_value < dealer_value || player_value > 21 {
-                    println!("Result Hand {}: Loss.", i + 1);
-                } else {
-                    println!("Result Hand {}: Push.", i + 1);
-                }
-                println!();
-            }
             PlayerAction::None
         }
         DealerRequest::Error(e) => {
             match e {
                 PlayerActionError::NotEnoughMoney(_, _) => println!("Not enough money."),
                 PlayerActionError::UnexpectedAction(_, _) => println!("Cannot perform action."),
+                PlayerActionError::SurrenderNotAllowed(_) => println!("Cannot surrender."),
             }
             PlayerAction::None
         }
@@ -120,10 +106,38 @@ fn main() {
 
     let mut dealer: Dealer;
 
-    dealer = Dealer::new(shoe, DEFAULT_CONFIG, &callback);
+    dealer = Dealer::new(
+        shoe,
+        GameConfig {
+            surrender: SurrenderRule::Late,
+            ..DEFAULT_CONFIG
+        },
+        &callback,
+    );
     dealer.players_mut().push(player);
 
     loop {
-        dealer.play_round(true);
+        let result = dealer.play_round(true);
+        // Print results of player hands
+        for (i, hand_result) in result.players[0].hands.iter().enumerate() {
+            let hand = &dealer.players()[0].hands()[i];
+            // Print hand
+            println!("Player Hand {}:", i + 1);
+            for card in hand.iter() {
+                print!("|{}|", card_to_printable(card));
+            }
+            // Print hand value
+            println!(" Total value: {}", get_hand_value(hand, true));
+            let outcome = match hand_result.outcome {
+                Outcome::Win => "Win!",
+                Outcome::Blackjack => "Blackjack!",
+                Outcome::Loss => "Loss.",
+                Outcome::Push => "Push.",
+                Outcome::Surrender => "Surrendered.",
+                Outcome::Bust => "Bust.",
+            };
+            println!("Result Hand {}: {} ({:+})", i + 1, outcome, hand_result.net);
+            println!();
+        }
     }
 }
@@ -0,0 +1,538 @@
+//! Exact expected values by combinatorial analysis of the remaining shoe
+//!
+//! Probabilities are computed from the exact composition of the cards left to be dealt,
+//! so the results change as cards are removed from the shoe.
+//!
+//! A few common simplifications are made:
+//! the cards drawn by the dealer are assumed to not have been affected by the player's
+//! knowledge that the dealer doesn't have blackjack, split hands are played
+//! independently of each other, and pairs are only split once.
+use crate::cards::{Card, Rank};
+use crate::game::{Dealer, GameConfig, HandValue, HoleCardRule, PlayerAction, SurrenderRule};
+use crate::shoe::Shoe;
+use std::collections::HashMap;
+
+/// The amount of cards of each value left in a shoe
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::analysis::Composition;
+/// use twentyone::shoe::Shoe;
+///
+/// let mut composition = Composition::from(&Shoe::new(6));
+/// assert_eq!(composition.len(), 312);
+/// assert_eq!(composition.count(10), 96);
+/// composition.remove("AS".parse().unwrap());
+/// assert_eq!(composition.count(11), 23);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Composition {
+    /// The amount of cards of each value from 2 to 11 (an ace)
+    counts: [u16; 10],
+}
+
+/// The chances of each of the dealer's final hands
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DealerProbabilities {
+    /// The chance of finishing on each total, indexed by the total
+    ///
+    /// Totals below 17 are only possible if the shoe runs out.
+    pub totals: [f64; 22],
+    /// The chance of busting
+    pub bust: f64,
+    /// The chance of having blackjack
+    pub blackjack: f64,
+}
+
+/// The expected value of each action on a hand, per unit of the original bet
+///
+/// Actions that aren't allowed on the hand are `None`.
+/// In games where the dealer peeks for blackjack, the values are for once the dealer
+/// has checked that they don't have blackjack.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExpectedValues {
+    /// Standing
+    pub stand: f64,
+    /// Hitting, then playing the best way afterwards
+    pub hit: f64,
+    /// Doubling down
+    pub double: Option<f64>,
+    /// Splitting once, then playing each hand the best way
+    pub split: Option<f64>,
+    /// Surrendering
+    pub surrender: Option<f64>,
+}
+
+/// Computes dealer probabilities and player expected values for a game's rules
+///
+/// Results are memoized, so reusing an analyzer for the same shoe is much faster.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::analysis::{Analyzer, Composition};
+/// use twentyone::cards::parse_cards;
+/// use twentyone::game::{PlayerAction, DEFAULT_CONFIG};
+///
+/// let mut analyzer = Analyzer::new(&DEFAULT_CONFIG);
+/// let hand = parse_cards("6S 5H").unwrap();
+/// let up_card = "6D".parse().unwrap();
+/// // Six decks without the cards on the table
+/// let mut shoe = Composition::from(&twentyone::shoe::Shoe::new(6));
+/// for &card in hand.iter().chain([up_card].iter()) {
+///     shoe.remove(card);
+/// }
+///
+/// let values = analyzer.expected_values(&hand, up_card, &shoe);
+/// assert!(values.double.unwrap() > values.hit);
+/// assert_eq!(values.best(), PlayerAction::DoubleDown);
+/// ```
+pub struct Analyzer {
+    rules: GameConfig,
+    /// The dealer's final hands, keyed by the shoe and the dealer's up card
+    dealer: HashMap<(Composition, u8), [f64; 23]>,
+    /// The dealer's final hands from a partial hand, keyed by the shoe, hard total,
+    /// whether it has an ace and the amount of cards
+    dealer_states: HashMap<(Composition, u8, bool, u8), [f64; 23]>,
+    /// The value of hitting, keyed by the shoe, hard total, whether it has an ace
+    /// and the dealer's up card
+    hits: HashMap<(Composition, u8, bool, u8), f64>,
+}
+
+/// The index of a dealer bust in a dealer distribution
+const BUST: usize = 22;
+
+impl Composition {
+    /// Returns the composition of some cards
+    ///
+    /// # Arguments
+    ///
+    /// * `cards` - The cards to count
+    pub fn new<'c, I: IntoIterator<Item = &'c Card>>(cards: I) -> Composition {
+        let mut composition = Composition::default();
+        for &card in cards {
+            composition.counts[index(card.value())] += 1;
+        }
+        composition
+    }
+
+    /// Returns the composition of the cards a dealer's players haven't seen:
+    /// the cards left in the shoe and the dealer's hole card
+    ///
+    /// # Arguments
+    ///
+    /// * `dealer` - The dealer to get the unseen cards of
+    pub fn unseen(dealer: &Dealer) -> Composition {
+        let hole_cards = dealer.hand().iter().skip(1);
+        Composition::new(dealer.shoe().cards().iter().chain(hole_cards))
+    }
+
+    /// Returns the amount of cards with a value
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the cards, from 2 to 11 (an ace)
+    pub fn count(&self, value: u8) -> u16 {
+        self.counts[index(value)]
+    }
+
+    /// Returns the total amount of cards
+    pub fn len(&self) -> usize {
+        self.counts.iter().map(|&count| count as usize).sum()
+    }
+
+    /// Returns whether there are no cards
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take a card out, returning whether there was one to take
+    ///
+    /// # Arguments
+    ///
+    /// * `card` - The card to take out
+    pub fn remove(&mut self, card: Card) -> bool {
+        let count = &mut self.counts[index(card.value())];
+        if *count == 0 {
+            return false;
+        }
+        *count -= 1;
+        true
+    }
+
+    /// Put a card in
+    ///
+    /// # Arguments
+    ///
+    /// * `card` - The card to put in
+    pub fn add(&mut self, card: Card) {
+        self.counts[index(card.value())] += 1;
+    }
+
+    /// Returns the composition with one card of a value taken out
+    fn without(mut self, value: u8) -> Composition {
+        self.counts[index(value)] -= 1;
+        self
+    }
+
+    /// Returns each value that is left with the chance of drawing it,
+    /// leaving out a value that can't be drawn
+    fn draws(&self, excluded: Option<u8>) -> Vec<(u8, f64)> {
+        let excluded_count = excluded.map_or(0, |value| self.count(value) as usize);
+        let total = self.len() - excluded_count;
+        (2..=11)
+            .filter(|&value| Some(value) != excluded && self.count(value) > 0)
+            .map(|value| (value, self.count(value) as f64 / total as f64))
+            .collect()
+    }
+}
+
+/// Returns the composition of the cards left in a shoe
+impl From<&Shoe> for Composition {
+    fn from(shoe: &Shoe) -> Composition {
+        Composition::new(shoe.cards())
+    }
+}
+
+impl DealerProbabilities {
+    /// Returns the chance of the dealer finishing on a total from 17 to 21, without blackjack
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - The total to get the chance of
+    pub fn total(&self, total: u8) -> f64 {
+        self.totals.get(total as usize).copied().unwrap_or(0.0)
+    }
+}
+
+impl ExpectedValues {
+    /// Returns the action with the highest expected value
+    pub fn best(&self) -> PlayerAction {
+        let mut best = (PlayerAction::Stand, self.stand);
+        let options = [
+            (PlayerAction::Hit, Some(self.hit)),
+            (PlayerAction::DoubleDown, self.double),
+            (PlayerAction::Split, self.split),
+            (PlayerAction::Surrender, self.surrender),
+        ];
+        for &(action, value) in options.iter() {
+            if let Some(value) = value {
+                if value > best.1 {
+                    best = (action, value);
+                }
+            }
+        }
+        best.0
+    }
+}
+
+impl Analyzer {
+    /// Returns a new Analyzer
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - The rules of the game
+    pub fn new(rules: &GameConfig) -> Analyzer {
+        Analyzer {
+            rules: *rules,
+            dealer: HashMap::new(),
+            dealer_states: HashMap::new(),
+            hits: HashMap::new(),
+        }
+    }
+
+    /// Returns the chances of each of the dealer's final hands
+    ///
+    /// # Arguments
+    ///
+    /// * `up_card` - The dealer's up card
+    /// * `shoe` - The cards left to be dealt, including the dealer's hole card
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::analysis::{Analyzer, Composition};
+    /// use twentyone::game::DEFAULT_CONFIG;
+    ///
+    /// let mut analyzer = Analyzer::new(&DEFAULT_CONFIG);
+    /// let shoe = Composition::new(&twentyone::cards::create_shoe(6));
+    /// let odds = analyzer.dealer_probabilities("6D".parse().unwrap(), &shoe);
+    /// assert!(odds.bust > 0.4);
+    /// ```
+    pub fn dealer_probabilities(
+        &mut self,
+        up_card: Card,
+        shoe: &Composition,
+    ) -> DealerProbabilities {
+        let blackjack = blackjack_chance(up_card.value(), shoe);
+        let finals = self.dealer_finals(*shoe, up_card.value());
+        let mut totals = [0.0; 22];
+        for (total, chance) in totals.iter_mut().enumerate() {
+            *chance = finals[total] * (1.0 - blackjack);
+        }
+        DealerProbabilities {
+            totals,
+            bust: finals[BUST] * (1.0 - blackjack),
+            blackjack,
+        }
+    }
+
+    /// Returns the expected value of each action on a hand
+    ///
+    /// # Arguments
+    ///
+    /// * `hand` - The player's hand
+    /// * `up_card` - The dealer's up card
+    /// * `shoe` - The cards left to be dealt, including the dealer's hole card
+    ///   (see [`Composition::unseen`])
+    pub fn expected_values(
+        &mut self,
+        hand: &[Card],
+        up_card: Card,
+        shoe: &Composition,
+    ) -> ExpectedValues {
+        let up = up_card.value();
+        let value = HandValue::new(hand);
+        let (hard, ace) = hand_state(hand);
+        let first_action = hand.len() == 2;
+
+        let stand = if value.is_blackjack {
+            self.rules.blackjack_payout as f64
+        } else {
+            self.stand(*shoe, value.total, up)
+        };
+        let hit = if value.is_bust {
+            -1.0
+        } else {
+            self.hit(*shoe, hard, ace, up)
+        };
+        let double = if first_action && self.rules.doubling_down {
+            Some(self.double(*shoe, hard, ace, up))
+        } else {
+            None
+        };
+        let split = if value.is_pair && self.rules.splitting {
+            let card = hand[0].value();
+            Some(2.0 * self.split_hand(*shoe, card, up))
+        } else {
+            None
+        };
+        let surrender = if first_action && self.rules.surrender != SurrenderRule::None {
+            Some(-0.5)
+        } else {
+            None
+        };
+
+        // Without a hole card, the dealer's blackjack is only found after the player plays
+        let original_bets_only = match self.rules.hole_card {
+            HoleCardRule::Peek => {
+                return ExpectedValues {
+                    stand,
+                    hit,
+                    double,
+                    split,
+                    surrender,
+                }
+            }
+            HoleCardRule::NoHoleCard { original_bets_only } => original_bets_only,
+        };
+        let blackjack = blackjack_chance(up, shoe);
+        let mix = |value: f64, loss: f64| value * (1.0 - blackjack) - loss * blackjack;
+        let extra_loss = if original_bets_only { 1.0 } else { 2.0 };
+        ExpectedValues {
+            stand: mix(stand, if value.is_blackjack { 0.0 } else { 1.0 }),
+            hit: mix(hit, 1.0),
+            double: double.map(|value| mix(value, extra_loss)),
+            split: split.map(|value| mix(value, extra_loss)),
+            surrender: surrender.map(|value| match self.rules.surrender {
+                SurrenderRule::Early => value,
+                _ => mix(value, 1.0),
+            }),
+        }
+    }
+
+    /// Returns the value of standing, without a dealer blackjack
+    fn stand(&mut self, shoe: Composition, total: u8, up: u8) -> f64 {
+        if total > 21 {
+            return -1.0;
+        }
+        let finals = self.dealer_finals(shoe, up);
+        let mut value = finals[BUST];
+        for (dealer, &chance) in finals[..BUST].iter().enumerate() {
+            let dealer = dealer as u8;
+            if total > dealer {
+                value += chance;
+            } else if total < dealer {
+                value -= chance;
+            }
+        }
+        value
+    }
+
+    /// Returns the value of hitting, then playing the best way afterwards
+    fn hit(&mut self, shoe: Composition, hard: u8, ace: bool, up: u8) -> f64 {
+        let key = (shoe, hard, ace, up);
+        if let Some(&value) = self.hits.get(&key) {
+            return value;
+        }
+
+        let value = if shoe.is_empty() {
+            self.stand(shoe, soft_total(hard, ace), up)
+        } else {
+            let mut value = 0.0;
+            for (card, chance) in shoe.draws(None) {
+                let (hard, ace) = add_card(hard, ace, card);
+                value += chance * self.play(shoe.without(card), hard, ace, up);
+            }
+            value
+        };
+        self.hits.insert(key, value);
+        value
+    }
+
+    /// Returns the value of a hand when hitting or standing, whichever is better
+    fn play(&mut self, shoe: Composition, hard: u8, ace: bool, up: u8) -> f64 {
+        let total = soft_total(hard, ace);
+        if total > 21 {
+            return -1.0;
+        }
+        let stand = self.stand(shoe, total, up);
+        if total == 21 {
+            return stand;
+        }
+        stand.max(self.hit(shoe, hard, ace, up))
+    }
+
+    /// Returns the value of doubling down
+    fn double(&mut self, shoe: Composition, hard: u8, ace: bool, up: u8) -> f64 {
+        if shoe.is_empty() {
+            return 2.0 * self.stand(shoe, soft_total(hard, ace), up);
+        }
+        let mut value = 0.0;
+        for (card, chance) in shoe.draws(None) {
+            let (hard, ace) = add_card(hard, ace, card);
+            value += chance * 2.0 * self.stand(shoe.without(card), soft_total(hard, ace), up);
+        }
+        value
+    }
+
+    /// Returns the value of one of the hands from splitting a pair
+    ///
+    /// `shoe` must not include either of the pair's cards.
+    fn split_hand(&mut self, shoe: Composition, card: u8, up: u8) -> f64 {
+        let aces = card == 11;
+        let can_hit = !aces || self.rules.hit_split_aces;
+        let can_double = can_hit && self.rules.doubling_down && self.rules.double_after_split;
+        let (hard, ace) = add_card(0, false, card);
+
+        let mut value = 0.0;
+        for (drawn, chance) in shoe.draws(None) {
+            let shoe = shoe.without(drawn);
+            let (hard, ace) = add_card(hard, ace, drawn);
+            let total = soft_total(hard, ace);
+            let hand_value = if aces && total == 21 && self.rules.split_ace_blackjack {
+                self.rules.blackjack_payout as f64
+            } else if !can_hit {
+                self.stand(shoe, total, up)
+            } else if can_double {
+                let play = self.play(shoe, hard, ace, up);
+                play.max(self.double(shoe, hard, ace, up))
+            } else {
+                self.play(shoe, hard, ace, up)
+            };
+            value += chance * hand_value;
+        }
+        value
+    }
+
+    /// Returns the dealer's final hands from an up card, given that they don't have blackjack
+    fn dealer_finals(&mut self, shoe: Composition, up: u8) -> [f64; 23] {
+        if let Some(&finals) = self.dealer.get(&(shoe, up)) {
+            return finals;
+        }
+        let (hard, ace) = add_card(0, false, up);
+        let finals = self.dealer_state(shoe, hard, ace, 1);
+        self.dealer.insert((shoe, up), finals);
+        finals
+    }
+
+    /// Returns the dealer's final hands from a partial hand
+    fn dealer_state(&mut self, shoe: Composition, hard: u8, ace: bool, cards: u8) -> [f64; 23] {
+        let key = (shoe, hard, ace, cards.min(2));
+        if let Some(&finals) = self.dealer_states.get(&key) {
+            return finals;
+        }
+
+        let total = soft_total(hard, ace);
+        let soft = ace && hard + 10 <= 21;
+        let hits = total < 17 || (total == 17 && soft && !self.rules.stand_soft_17);
+        // The hole card can't make a blackjack, since the dealer doesn't have one
+        let excluded = match (cards, hard) {
+            (1, 1) => Some(10),
+            (1, 10) => Some(11),
+            _ => None,
+        };
+        let can_draw = shoe.len() > excluded.map_or(0, |value| shoe.count(value) as usize);
+
+        let mut finals = [0.0; 23];
+        if total > 21 {
+            finals[BUST] = 1.0;
+        } else if (cards >= 2 && !hits) || !can_draw {
+            finals[total as usize] = 1.0;
+        } else {
+            for (card, chance) in shoe.draws(excluded) {
+                let (hard, ace) = add_card(hard, ace, card);
+                let next = self.dealer_state(shoe.without(card), hard, ace, cards + 1);
+                for (final_chance, next_chance) in finals.iter_mut().zip(next.iter()) {
+                    *final_chance += chance * next_chance;
+                }
+            }
+        }
+        self.dealer_states.insert(key, finals);
+        finals
+    }
+}
+
+/// Returns the index of a card value in a composition
+fn index(value: u8) -> usize {
+    (value.clamp(2, 11) - 2) as usize
+}
+
+/// Returns the hard total of a hand and whether it has an ace
+fn hand_state(hand: &[Card]) -> (u8, bool) {
+    hand.iter().fold((0, false), |(hard, ace), card| {
+        add_card(hard, ace, card.value())
+    })
+}
+
+/// Returns a hard total and whether there is an ace after adding a card value
+fn add_card(hard: u8, ace: bool, value: u8) -> (u8, bool) {
+    if value == Rank::Ace.value() {
+        (hard + 1, true)
+    } else {
+        (hard + value, ace)
+    }
+}
+
+/// Returns the best total of a hand from its hard total and whether it has an ace
+fn soft_total(hard: u8, ace: bool) -> u8 {
+    if ace && hard + 10 <= 21 {
+        hard + 10
+    } else {
+        hard
+    }
+}
+
+/// Returns the chance of the dealer having blackjack with an up card
+fn blackjack_chance(up: u8, shoe: &Composition) -> f64 {
+    let needed = match up {
+        11 => 10,
+        10 => 11,
+        _ => return 0.0,
+    };
+    if shoe.is_empty() {
+        return 0.0;
+    }
+    shoe.count(needed) as f64 / shoe.len() as f64
+}
@@ -0,0 +1,542 @@
+//! Betting policies that decide how much to bet each round
+//!
+//! Policies are given the player's bankroll, the result of the last round and the true count,
+//! and are wrapped in a [`BettingAgent`] to bet for a player.
+//! The agent keeps bets within the table limits and what the player can afford.
+use crate::cards::Card;
+use crate::counting::{Counter, CountingSystem};
+use crate::game::{
+    Dealer, GameConfig, Player, PlayerAction, PlayerActionError, PlayerAgent, SideBetKind,
+};
+
+/// What a betting policy knows before a round
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BetInfo {
+    /// The money the player has
+    pub bankroll: i32,
+    /// The amount won (or lost, if negative) in the last round,
+    /// or `None` if the player sat it out or hasn't played yet
+    pub last_net: Option<i32>,
+    /// The current true count
+    pub true_count: f32,
+    /// The table's minimum bet
+    pub min_bet: i32,
+    /// The table's maximum bet
+    pub max_bet: i32,
+}
+
+/// Decides how much to bet each round
+///
+/// # Examples
+///
+/// A policy that bets a tenth of the bankroll:
+///
+/// ```
+/// use twentyone::betting::{BetInfo, BettingPolicy};
+///
+/// struct Tenth;
+///
+/// impl BettingPolicy for Tenth {
+///     fn bet(&mut self, info: &BetInfo) -> Option<i32> {
+///         Some(info.bankroll / 10)
+///     }
+/// }
+/// ```
+pub trait BettingPolicy {
+    /// Returns the amount to bet on the next round, or `None` to sit it out
+    ///
+    /// The amount doesn't need to be within the table limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `info` - The bankroll, last result and count before the round
+    fn bet(&mut self, info: &BetInfo) -> Option<i32>;
+}
+
+/// Bets the same amount every round
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Flat {
+    bet: i32,
+}
+
+/// Bets more units as the true count rises
+///
+/// One unit is bet below the starting count, then two units at the starting count,
+/// and one more unit for each true count above it, up to the spread.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrueCountRamp {
+    unit: i32,
+    spread: i32,
+    start: f32,
+}
+
+/// Bets a fraction of the Kelly criterion, using the advantage estimated from the true count
+///
+/// The minimum bet is made when the player doesn't have the advantage.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Kelly {
+    /// The fraction of the full Kelly bet to make (eg. `0.5` for half Kelly)
+    pub fraction: f64,
+    /// The player's advantage at a true count of zero
+    pub base_edge: f64,
+    /// The advantage gained for each true count
+    pub edge_per_count: f64,
+    /// The variance of a round, in units of the bet squared
+    pub variance: f64,
+}
+
+/// Doubles the bet after every loss, going back to one unit after a win
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Martingale {
+    unit: i32,
+    bet: i32,
+}
+
+/// Doubles the bet after every win, going back to one unit after a loss
+/// or once the wins in a row have been reached
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Paroli {
+    unit: i32,
+    max_wins: u32,
+    wins: u32,
+}
+
+/// Bets 1, 3, 2 then 6 units over a run of wins, going back to one unit
+/// after a loss or once the run is finished
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OneThreeTwoSix {
+    unit: i32,
+    step: usize,
+}
+
+/// Only plays while the true count is high enough, using another policy to bet
+///
+/// The player starts playing once the count reaches the entry count,
+/// and sits out once it falls below the exit count.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wonging<P: BettingPolicy> {
+    policy: P,
+    enter: f32,
+    exit: f32,
+    playing: bool,
+}
+
+impl Flat {
+    /// Returns a new Flat policy
+    ///
+    /// # Arguments
+    ///
+    /// * `bet` - The amount to bet every round
+    pub fn new(bet: i32) -> Flat {
+        Flat { bet }
+    }
+}
+
+impl BettingPolicy for Flat {
+    fn bet(&mut self, _info: &BetInfo) -> Option<i32> {
+        Some(self.bet)
+    }
+}
+
+impl TrueCountRamp {
+    /// Returns a new TrueCountRamp
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The smallest bet
+    /// * `spread` - The most units to bet
+    /// * `start` - The true count to start raising the bet at
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::betting::{BetInfo, BettingPolicy, TrueCountRamp};
+    ///
+    /// // A 1 to 8 spread, raising the bet from a true count of 2
+    /// let mut ramp = TrueCountRamp::new(10, 8, 2.0);
+    /// let mut info = BetInfo {
+    ///     bankroll: 10000,
+    ///     last_net: None,
+    ///     true_count: 1.5,
+    ///     min_bet: 10,
+    ///     max_bet: 500,
+    /// };
+    /// assert_eq!(ramp.bet(&info), Some(10));
+    /// info.true_count = 3.2;
+    /// assert_eq!(ramp.bet(&info), Some(30));
+    /// info.true_count = 12.0;
+    /// assert_eq!(ramp.bet(&info), Some(80));
+    /// ```
+    pub fn new(unit: i32, spread: i32, start: f32) -> TrueCountRamp {
+        TrueCountRamp {
+            unit,
+            spread,
+            start,
+        }
+    }
+}
+
+impl BettingPolicy for TrueCountRamp {
+    fn bet(&mut self, info: &BetInfo) -> Option<i32> {
+        let units = if info.true_count < self.start {
+            1
+        } else {
+            2 + (info.true_count - self.start) as i32
+        };
+        Some(self.unit * units.clamp(1, self.spread.max(1)))
+    }
+}
+
+impl Kelly {
+    /// Returns a new Kelly policy with estimates for Hi-Lo in a six-deck game
+    ///
+    /// The player is assumed to have a -0.5% advantage at a true count of zero,
+    /// gaining 0.5% for each true count, with a variance of 1.3.
+    ///
+    /// # Arguments
+    ///
+    /// * `fraction` - The fraction of the full Kelly bet to make
+    pub fn new(fraction: f64) -> Kelly {
+        Kelly {
+            fraction,
+            base_edge: -0.005,
+            edge_per_count: 0.005,
+            variance: 1.3,
+        }
+    }
+
+    /// Returns the player's estimated advantage at a true count
+    ///
+    /// # Arguments
+    ///
+    /// * `true_count` - The true count
+    pub fn edge(&self, true_count: f32) -> f64 {
+        self.base_edge + self.edge_per_count * true_count as f64
+    }
+}
+
+impl BettingPolicy for Kelly {
+    fn bet(&mut self, info: &BetInfo) -> Option<i32> {
+        let edge = self.edge(info.true_count);
+        if edge <= 0.0 {
+            return Some(info.min_bet);
+        }
+        let bet = info.bankroll as f64 * self.fraction * edge / self.variance;
+        Some(bet.min(i32::MAX as f64) as i32)
+    }
+}
+
+impl Martingale {
+    /// Returns a new Martingale policy
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The first bet, which is bet again after every win
+    pub fn new(unit: i32) -> Martingale {
+        Martingale { unit, bet: unit }
+    }
+}
+
+impl BettingPolicy for Martingale {
+    fn bet(&mut self, info: &BetInfo) -> Option<i32> {
+        match info.last_net {
+            Some(net) if net < 0 => self.bet = self.bet.saturating_mul(2),
+            Some(net) if net > 0 => self.bet = self.unit,
+            _ => (),
+        }
+        // Start over once the doubled bet can't be made
+        if self.bet > info.max_bet || self.bet > info.bankroll {
+            self.bet = self.unit;
+        }
+        Some(self.bet)
+    }
+}
+
+impl Paroli {
+    /// Returns a new Paroli policy that stops after three wins in a row
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The first bet
+    pub fn new(unit: i32) -> Paroli {
+        Paroli::with_wins(unit, 3)
+    }
+
+    /// Returns a new Paroli policy that stops after a number of wins in a row
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The first bet
+    /// * `max_wins` - The wins in a row to go back to one unit after
+    pub fn with_wins(unit: i32, max_wins: u32) -> Paroli {
+        Paroli {
+            unit,
+            max_wins,
+            wins: 0,
+        }
+    }
+}
+
+impl BettingPolicy for Paroli {
+    fn bet(&mut self, info: &BetInfo) -> Option<i32> {
+        match info.last_net {
+            Some(net) if net > 0 => self.wins += 1,
+            Some(net) if net < 0 => self.wins = 0,
+            _ => (),
+        }
+        if self.wins >= self.max_wins {
+            self.wins = 0;
+        }
+        Some(self.unit.saturating_mul(1 << self.wins.min(30)))
+    }
+}
+
+impl OneThreeTwoSix {
+    /// The units bet at each step of a run
+    const UNITS: [i32; 4] = [1, 3, 2, 6];
+
+    /// Returns a new OneThreeTwoSix policy
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The first bet
+    pub fn new(unit: i32) -> OneThreeTwoSix {
+        OneThreeTwoSix { unit, step: 0 }
+    }
+}
+
+impl BettingPolicy for OneThreeTwoSix {
+    fn bet(&mut self, info: &BetInfo) -> Option<i32> {
+        match info.last_net {
+            Some(net) if net > 0 => self.step = (self.step + 1) % OneThreeTwoSix::UNITS.len(),
+            Some(net) if net < 0 => self.step = 0,
+            _ => (),
+        }
+        Some(self.unit * OneThreeTwoSix::UNITS[self.step])
+    }
+}
+
+impl<P: BettingPolicy> Wonging<P> {
+    /// Returns a new Wonging policy
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy to bet with while playing
+    /// * `enter` - The true count to start playing at
+    /// * `exit` - The true count to stop playing below
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::betting::{BetInfo, BettingPolicy, Flat, Wonging};
+    ///
+    /// let mut wonging = Wonging::new(Flat::new(10), 1.0, 0.0);
+    /// let mut info = BetInfo {
+    ///     bankroll: 1000,
+    ///     last_net: None,
+    ///     true_count: 0.5,
+    ///     min_bet: 10,
+    ///     max_bet: 500,
+    /// };
+    /// assert_eq!(wonging.bet(&info), None);
+    /// info.true_count = 1.0;
+    /// assert_eq!(wonging.bet(&info), Some(10));
+    /// // Keep playing until the count drops below the exit count
+    /// info.true_count = 0.5;
+    /// assert_eq!(wonging.bet(&info), Some(10));
+    /// info.true_count = -0.5;
+    /// assert_eq!(wonging.bet(&info), None);
+    /// ```
+    pub fn new(policy: P, enter: f32, exit: f32) -> Wonging<P> {
+        Wonging {
+            policy,
+            enter,
+            exit,
+            playing: false,
+        }
+    }
+
+    /// Returns whether the player is currently playing
+    pub fn playing(&self) -> bool {
+        self.playing
+    }
+}
+
+impl<P: BettingPolicy> BettingPolicy for Wonging<P> {
+    fn bet(&mut self, info: &BetInfo) -> Option<i32> {
+        if self.playing {
+            self.playing = info.true_count >= self.exit;
+        } else {
+            self.playing = info.true_count >= self.enter;
+        }
+        if self.playing {
+            self.policy.bet(info)
+        } else {
+            None
+        }
+    }
+}
+
+/// An agent that bets with a betting policy and plays with another agent
+///
+/// The agent keeps a count of the table for the policy's true count.
+/// Bets are kept within the table limits and the player's money,
+/// and the player sits out if they can't afford the minimum bet.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::betting::{BettingAgent, TrueCountRamp};
+/// use twentyone::counting::HiLo;
+/// use twentyone::prelude::*;
+/// use twentyone::strategy::BasicStrategyAgent;
+/// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+/// #     PlayerAction::None
+/// # }
+///
+/// let config = GameConfig {
+///     min_bet: 10,
+///     max_bet: 500,
+///     ..DEFAULT_CONFIG
+/// };
+/// let agent = BettingAgent::new(
+///     &config,
+///     BasicStrategyAgent::new(&config, 10),
+///     TrueCountRamp::new(10, 12, 1.0),
+///     HiLo,
+/// );
+///
+/// let mut shoe = create_shoe(6);
+/// shuffle_deck(&mut shoe);
+/// let mut dealer = Dealer::new(shoe, config, &callback);
+/// dealer.players_mut().push(Player::with_agent(1000, agent));
+/// dealer.play_round(true);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BettingAgent<A: PlayerAgent, P: BettingPolicy, S: CountingSystem> {
+    agent: A,
+    policy: P,
+    counter: Counter<S>,
+    min_bet: i32,
+    max_bet: i32,
+    /// The index of the player at the table, found when they are first asked to bet
+    seat: Option<usize>,
+    last_net: Option<i32>,
+}
+
+impl<A: PlayerAgent, P: BettingPolicy, S: CountingSystem> BettingAgent<A, P, S> {
+    /// Returns a new BettingAgent
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rules of the game, for the table limits and amount of decks
+    /// * `agent` - The agent that plays the player's hands
+    /// * `policy` - The policy used to bet
+    /// * `system` - The counting system used for the true count
+    pub fn new(config: &GameConfig, agent: A, policy: P, system: S) -> BettingAgent<A, P, S> {
+        BettingAgent {
+            agent,
+            policy,
+            counter: Counter::new(system, config.shoe_deck_count),
+            min_bet: config.min_bet,
+            max_bet: config.max_bet,
+            seat: None,
+            last_net: None,
+        }
+    }
+
+    /// Returns a reference to the agent that plays the player's hands
+    pub fn agent(&self) -> &A {
+        &self.agent
+    }
+
+    /// Returns a reference to the betting policy
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Returns a reference to the agent's count
+    pub fn counter(&self) -> &Counter<S> {
+        &self.counter
+    }
+}
+
+impl<A: PlayerAgent, P: BettingPolicy, S: CountingSystem> PlayerAgent for BettingAgent<A, P, S> {
+    fn bet(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        self.seat = dealer
+            .players()
+            .iter()
+            .position(|seat| std::ptr::eq(seat, player));
+
+        let bankroll = *player.money();
+        let info = BetInfo {
+            bankroll,
+            last_net: self.last_net,
+            true_count: self.counter.true_count(dealer.shoe()),
+            min_bet: self.min_bet,
+            max_bet: self.max_bet,
+        };
+        if bankroll < self.min_bet {
+            return PlayerAction::None;
+        }
+        match self.policy.bet(&info) {
+            Some(bet) => PlayerAction::Bet(bet.clamp(self.min_bet, self.max_bet).min(bankroll)),
+            None => PlayerAction::None,
+        }
+    }
+
+    fn play(&mut self, hand: usize, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        self.agent.play(hand, player, dealer)
+    }
+
+    fn surrender(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        self.agent.surrender(player, dealer)
+    }
+
+    fn insurance(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        self.agent.insurance(player, dealer)
+    }
+
+    fn side_bet(&mut self, kind: SideBetKind, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        self.agent.side_bet(kind, player, dealer)
+    }
+
+    fn up_card(&mut self, card: Card, dealer: &Dealer) {
+        self.counter.update(dealer);
+        self.agent.up_card(card, dealer);
+    }
+
+    fn hit_card(&mut self, card: Card, dealer: &Dealer) {
+        self.counter.update(dealer);
+        self.agent.hit_card(card, dealer);
+    }
+
+    fn dealer_hand(&mut self, hand: &[Card], dealer: &Dealer) {
+        self.counter.update(dealer);
+        // Remember the result of the round for the next bet
+        self.last_net = match (dealer.result(), self.seat) {
+            (Some(result), Some(seat)) => result
+                .players
+                .get(seat)
+                .filter(|result| !result.hands.is_empty())
+                .map(|result| result.net()),
+            _ => None,
+        };
+        self.agent.dealer_hand(hand, dealer);
+    }
+
+    fn low_cards(&mut self, dealer: &Dealer) {
+        self.counter.reset();
+        self.counter.update(dealer);
+        self.agent.low_cards(dealer);
+    }
+
+    fn error(&mut self, error: PlayerActionError, player: &Player, dealer: &Dealer) {
+        self.agent.error(error, player, dealer);
+    }
+}
@@ -1,5 +1,282 @@
 //! Card-related functions, such as deck creation and shuffling
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// The suit of a card
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+    Spades,
+    Hearts,
+    Clubs,
+    Diamonds,
+}
+
+/// The rank of a card, ordered from two to ace
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+/// A playing card
+///
+/// Cards are ordered by suit, then by rank, matching the order of
+/// [`create_deck`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Card {
+    pub suit: Suit,
+    pub rank: Rank,
+}
+
+/// Reason for a card being unable to be parsed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseCardError {
+    /// The input did not contain exactly a rank and a suit
+    InvalidLength,
+    /// The character used for the rank was not recognized
+    ///
+    /// # Arguments
+    ///
+    /// * `char` - The unrecognized character
+    InvalidRank(char),
+    /// The character used for the suit was not recognized
+    ///
+    /// # Arguments
+    ///
+    /// * `char` - The unrecognized character
+    InvalidSuit(char),
+}
+
+impl Suit {
+    /// All suits in deck order
+    pub const ALL: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Clubs, Suit::Diamonds];
+
+    /// Returns the character used for the suit (one of `S`, `H`, `C` or `D`)
+    pub fn to_char(self) -> char {
+        match self {
+            Suit::Spades => 'S',
+            Suit::Hearts => 'H',
+            Suit::Clubs => 'C',
+            Suit::Diamonds => 'D',
+        }
+    }
+
+    /// Returns the suit for a character, if it is valid
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - One of `S`, `H`, `C` or `D` (case-insensitive)
+    pub fn from_char(c: char) -> Option<Suit> {
+        match c.to_ascii_uppercase() {
+            'S' => Some(Suit::Spades),
+            'H' => Some(Suit::Hearts),
+            'C' => Some(Suit::Clubs),
+            'D' => Some(Suit::Diamonds),
+            _ => None,
+        }
+    }
+}
+
+impl Rank {
+    /// All ranks from two to ace
+    pub const ALL: [Rank; 13] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+
+    /// Returns the blackjack value of the rank
+    ///
+    /// Face cards are worth 10 and aces are worth 11.
+    pub fn value(self) -> u8 {
+        match self {
+            Rank::Two => 2,
+            Rank::Three => 3,
+            Rank::Four => 4,
+            Rank::Five => 5,
+            Rank::Six => 6,
+            Rank::Seven => 7,
+            Rank::Eight => 8,
+            Rank::Nine => 9,
+            Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => 10,
+            Rank::Ace => 11,
+        }
+    }
+
+    /// Returns the character used for the rank (one of `2`-`9`, `T`, `J`, `Q`, `K` or `A`)
+    pub fn to_char(self) -> char {
+        match self {
+            Rank::Two => '2',
+            Rank::Three => '3',
+            Rank::Four => '4',
+            Rank::Five => '5',
+            Rank::Six => '6',
+            Rank::Seven => '7',
+            Rank::Eight => '8',
+            Rank::Nine => '9',
+            Rank::Ten => 'T',
+            Rank::Jack => 'J',
+            Rank::Queen => 'Q',
+            Rank::King => 'K',
+            Rank::Ace => 'A',
+        }
+    }
+
+    /// Returns the rank for a character, if it is valid
+    ///
+    /// # Arguments
+    ///
+    /// * `c` - One of `2`-`9`, `T`, `J`, `Q`, `K` or `A` (case-insensitive)
+    pub fn from_char(c: char) -> Option<Rank> {
+        match c.to_ascii_uppercase() {
+            '2' => Some(Rank::Two),
+            '3' => Some(Rank::Three),
+            '4' => Some(Rank::Four),
+            '5' => Some(Rank::Five),
+            '6' => Some(Rank::Six),
+            '7' => Some(Rank::Seven),
+            '8' => Some(Rank::Eight),
+            '9' => Some(Rank::Nine),
+            'T' => Some(Rank::Ten),
+            'J' => Some(Rank::Jack),
+            'Q' => Some(Rank::Queen),
+            'K' => Some(Rank::King),
+            'A' => Some(Rank::Ace),
+            _ => None,
+        }
+    }
+}
+
+impl Card {
+    /// Returns a new Card
+    ///
+    /// # Arguments
+    ///
+    /// * `rank` - The rank of the card
+    /// * `suit` - The suit of the card
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::cards::{Card, Rank, Suit};
+    /// let card = Card::new(Rank::Ace, Suit::Spades);
+    /// assert_eq!(card.to_string(), "AS");
+    /// ```
+    pub fn new(rank: Rank, suit: Suit) -> Card {
+        Card { suit, rank }
+    }
+
+    /// Returns the blackjack value of the card
+    ///
+    /// Face cards are worth 10 and aces are worth 11.
+    pub fn value(self) -> u8 {
+        self.rank.value()
+    }
+}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+/// Formats a card as its rank followed by its suit (eg. `AS` for the ace of spades)
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.rank, self.suit)
+    }
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCardError::InvalidLength => write!(f, "a card must be a rank and a suit"),
+            ParseCardError::InvalidRank(c) => write!(f, "invalid rank '{}'", c),
+            ParseCardError::InvalidSuit(c) => write!(f, "invalid suit '{}'", c),
+        }
+    }
+}
+
+impl std::error::Error for ParseCardError {}
+
+/// Parses a card from its rank followed by its suit (eg. `AS` or `10H`)
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Card, ParseCardError> {
+        let s = s.trim();
+        // Allow "10" as an alternative to "T"
+        let (rank, suit) = if let Some(suit) = s.strip_prefix("10") {
+            (Rank::Ten, suit)
+        } else {
+            let mut chars = s.chars();
+            let c = chars.next().ok_or(ParseCardError::InvalidLength)?;
+            (
+                Rank::from_char(c).ok_or(ParseCardError::InvalidRank(c))?,
+                chars.as_str(),
+            )
+        };
+
+        let mut chars = suit.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Card {
+                suit: Suit::from_char(c).ok_or(ParseCardError::InvalidSuit(c))?,
+                rank,
+            }),
+            _ => Err(ParseCardError::InvalidLength),
+        }
+    }
+}
+
+/// Converts a card into the two-character `[suit, rank]` form
+impl From<Card> for [char; 2] {
+    fn from(card: Card) -> [char; 2] {
+        [card.suit.to_char(), card.rank.to_char()]
+    }
+}
+
+/// Converts a card from the two-character `[suit, rank]` form
+impl TryFrom<[char; 2]> for Card {
+    type Error = ParseCardError;
+
+    fn try_from(chars: [char; 2]) -> Result<Card, ParseCardError> {
+        Ok(Card {
+            suit: Suit::from_char(chars[0]).ok_or(ParseCardError::InvalidSuit(chars[0]))?,
+            rank: Rank::from_char(chars[1]).ok_or(ParseCardError::InvalidRank(chars[1]))?,
+        })
+    }
+}
 
 /// Returns a 52-card deck in order
 ///
@@ -9,17 +286,13 @@ use rand::seq::SliceRandom;
 /// use twentyone::cards;
 /// let mut deck = cards::create_deck();
 /// ```
-pub fn create_deck() -> Vec<[char; 2]> {
-    let mut deck = Vec::new();
-    let suits = ['S', 'H', 'C', 'D'];
-    let values = [
-        '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
-    ];
+pub fn create_deck() -> Vec<Card> {
+    let mut deck = Vec::with_capacity(52);
 
     // Generate deck
-    for i in 0..4 {
-        for j in 0..13 {
-            deck.push([suits[i], values[j]]);
+    for &suit in Suit::ALL.iter() {
+        for &rank in Rank::ALL.iter() {
+            deck.push(Card { suit, rank });
         }
     }
 
@@ -38,7 +311,7 @@ pub fn create_deck() -> Vec<[char; 2]> {
 /// use twentyone::cards;
 /// let mut shoe = cards::create_shoe(6);
 /// ```
-pub fn create_shoe(deck_count: u8) -> Vec<[char; 2]> {
+pub fn create_shoe(deck_count: u8) -> Vec<Card> {
     let mut shoe = Vec::new();
     for _ in 0..deck_count {
         shoe.append(&mut create_deck());
@@ -46,6 +319,32 @@ pub fn create_shoe(deck_count: u8) -> Vec<[char; 2]> {
     shoe
 }
 
+/// Returns the cards written in a string, in the same order
+///
+/// Cards are written as their rank followed by their suit (eg. `AS` or `10H`),
+/// separated by whitespace or commas. This is useful for stacking a deck to test
+/// specific scenarios.
+///
+/// # Arguments
+///
+/// * `s` - The cards to parse
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::{self, Card, Rank, Suit};
+/// let deck = cards::parse_cards("AS KH, 8D 8C").unwrap();
+/// assert_eq!(deck.len(), 4);
+/// assert_eq!(deck[0], Card::new(Rank::Ace, Suit::Spades));
+/// assert!(cards::parse_cards("AS XH").is_err());
+/// ```
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    s.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|card| !card.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
 /// Shuffles a deck or shoe into a random order
 ///
 /// # Arguments
@@ -59,13 +358,58 @@ pub fn create_shoe(deck_count: u8) -> Vec<[char; 2]> {
 /// let mut deck = cards::create_deck();
 /// cards::shuffle_deck(&mut deck);
 /// ```
-pub fn shuffle_deck(deck: &mut Vec<[char; 2]>) {
-    let mut rng = rand::thread_rng();
-    deck.shuffle(&mut rng);
+pub fn shuffle_deck(deck: &mut [Card]) {
+    shuffle_deck_with(deck, &mut rand::thread_rng());
+}
+
+/// Shuffles a deck or shoe into a random order using the provided random number generator
+///
+/// # Arguments
+///
+/// * `deck` - The deck or shoe to shuffle
+/// * `rng` - The random number generator to shuffle with
+///
+/// # Examples
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use twentyone::cards;
+/// let mut rng = StdRng::seed_from_u64(21);
+/// let mut deck = cards::create_deck();
+/// cards::shuffle_deck_with(&mut deck, &mut rng);
+/// ```
+pub fn shuffle_deck_with<R: Rng + ?Sized>(deck: &mut [Card], rng: &mut R) {
+    deck.shuffle(rng);
+}
+
+/// Shuffles a deck or shoe into an order determined by a seed
+///
+/// The same seed will always produce the same order.
+///
+/// # Arguments
+///
+/// * `deck` - The deck or shoe to shuffle
+/// * `seed` - The seed to shuffle with
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards;
+/// let mut first = cards::create_shoe(6);
+/// let mut second = cards::create_shoe(6);
+/// cards::shuffle_deck_seeded(&mut first, 21);
+/// cards::shuffle_deck_seeded(&mut second, 21);
+/// assert_eq!(first, second);
+/// ```
+pub fn shuffle_deck_seeded(deck: &mut [Card], seed: u64) {
+    shuffle_deck_with(deck, &mut StdRng::seed_from_u64(seed));
 }
 
 /// Returns the first card from a deck or shoe, then removes it
 ///
+/// Returns `Err(())` if the deck is empty.
+///
 /// # Arguments
 ///
 /// * `deck` - The deck or shoe to draw from
@@ -77,13 +421,12 @@ pub fn shuffle_deck(deck: &mut Vec<[char; 2]>) {
 /// let mut deck = cards::create_deck();
 /// let card = cards::draw_card(&mut deck);
 /// ```
-pub fn draw_card(deck: &mut Vec<[char; 2]>) -> Result<[char; 2], ()> {
-    if deck.len() > 0 {
-        let card = deck[0];
-        deck.remove(0);
-        Ok(card)
-    } else {
+#[allow(clippy::result_unit_err)]
+pub fn draw_card(deck: &mut Vec<Card>) -> Result<Card, ()> {
+    if deck.is_empty() {
         Err(())
+    } else {
+        Ok(deck.remove(0))
     }
 }
 
@@ -102,7 +445,7 @@ pub fn draw_card(deck: &mut Vec<[char; 2]>) -> Result<[char; 2], ()> {
 /// let mut hand = Vec::new();
 /// cards::hit_card(&mut shoe, &mut hand);
 /// ```
-pub fn hit_card(source: &mut Vec<[char; 2]>, target: &mut Vec<[char; 2]>) {
+pub fn hit_card(source: &mut Vec<Card>, target: &mut Vec<Card>) {
     let card = draw_card(source).unwrap();
     target.push(card);
 }
@@ -0,0 +1,351 @@
+//! Card counting systems and a counter that keeps track of the count at the table
+use crate::cards::{Card, Rank};
+use crate::game::{Dealer, DealerRequest};
+use crate::shoe::Shoe;
+
+/// A card counting system, which gives each rank a tag added to the running count
+///
+/// # Examples
+///
+/// A custom system that only counts fives and aces:
+///
+/// ```
+/// use twentyone::cards::Rank;
+/// use twentyone::counting::CountingSystem;
+///
+/// struct AceFive;
+///
+/// impl CountingSystem for AceFive {
+///     fn name(&self) -> &'static str {
+///         "Ace/Five"
+///     }
+///
+///     fn tag(&self, rank: Rank) -> f32 {
+///         match rank {
+///             Rank::Five => 1.0,
+///             Rank::Ace => -1.0,
+///             _ => 0.0,
+///         }
+///     }
+/// }
+///
+/// assert!(AceFive.balanced());
+/// assert_eq!(AceFive.initial_count(6), 0.0);
+/// ```
+pub trait CountingSystem {
+    /// Returns the name of the system
+    fn name(&self) -> &'static str;
+
+    /// Returns the tag of a rank
+    ///
+    /// # Arguments
+    ///
+    /// * `rank` - The rank to get the tag of
+    fn tag(&self, rank: Rank) -> f32;
+
+    /// Returns whether the tags of a full deck add up to zero
+    fn balanced(&self) -> bool {
+        Rank::ALL.iter().map(|&rank| self.tag(rank)).sum::<f32>() == 0.0
+    }
+
+    /// Returns the running count of a freshly shuffled shoe
+    ///
+    /// Balanced systems start at zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `decks` - The amount of decks in the shoe
+    fn initial_count(&self, _decks: u8) -> f32 {
+        0.0
+    }
+}
+
+/// The Hi-Lo system, a balanced level one count
+///
+/// Twos to sixes are +1, sevens to nines are 0, and tens and aces are -1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HiLo;
+
+/// The Knock-Out (KO) system, an unbalanced level one count
+///
+/// Twos to sevens are +1, eights and nines are 0, and tens and aces are -1.
+/// The running count starts at `4 - 4 * decks`, so that it is used without a true count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KnockOut;
+
+/// The Omega II system, a balanced level two count that doesn't count aces
+///
+/// Twos, threes and sevens are +1, fours to sixes are +2, eights and aces are 0,
+/// nines are -1 and tens are -2.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OmegaII;
+
+/// The Zen count, a balanced level two count
+///
+/// Twos, threes and sevens are +1, fours to sixes are +2, eights and nines are 0,
+/// tens are -2 and aces are -1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Zen;
+
+/// The Wong Halves system, a balanced level three count with half-point tags
+///
+/// Twos and sevens are +0.5, threes, fours and sixes are +1, fives are +1.5,
+/// eights are 0, nines are -0.5, and tens and aces are -1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WongHalves;
+
+impl CountingSystem for HiLo {
+    fn name(&self) -> &'static str {
+        "Hi-Lo"
+    }
+
+    fn tag(&self, rank: Rank) -> f32 {
+        match rank.value() {
+            2..=6 => 1.0,
+            7..=9 => 0.0,
+            _ => -1.0,
+        }
+    }
+}
+
+impl CountingSystem for KnockOut {
+    fn name(&self) -> &'static str {
+        "KO"
+    }
+
+    fn tag(&self, rank: Rank) -> f32 {
+        match rank.value() {
+            2..=7 => 1.0,
+            8 | 9 => 0.0,
+            _ => -1.0,
+        }
+    }
+
+    fn initial_count(&self, decks: u8) -> f32 {
+        4.0 - 4.0 * decks as f32
+    }
+}
+
+impl CountingSystem for OmegaII {
+    fn name(&self) -> &'static str {
+        "Omega II"
+    }
+
+    fn tag(&self, rank: Rank) -> f32 {
+        match rank.value() {
+            2 | 3 | 7 => 1.0,
+            4..=6 => 2.0,
+            9 => -1.0,
+            10 => -2.0,
+            _ => 0.0,
+        }
+    }
+}
+
+impl CountingSystem for Zen {
+    fn name(&self) -> &'static str {
+        "Zen"
+    }
+
+    fn tag(&self, rank: Rank) -> f32 {
+        match rank.value() {
+            2 | 3 | 7 => 1.0,
+            4..=6 => 2.0,
+            8 | 9 => 0.0,
+            10 => -2.0,
+            _ => -1.0,
+        }
+    }
+}
+
+impl CountingSystem for WongHalves {
+    fn name(&self) -> &'static str {
+        "Wong Halves"
+    }
+
+    fn tag(&self, rank: Rank) -> f32 {
+        match rank.value() {
+            2 | 7 => 0.5,
+            3 | 4 | 6 => 1.0,
+            5 => 1.5,
+            8 => 0.0,
+            9 => -0.5,
+            _ => -1.0,
+        }
+    }
+}
+
+/// Keeps a running count of the cards seen at a table
+///
+/// The counter is kept up to date by showing it the dealer with
+/// [`observe`](Counter::observe) or [`update`](Counter::update).
+/// Every card visible on the table is counted once, including other players' cards.
+/// The dealer's hole card is only counted once the round is over,
+/// and burned cards are never counted.
+///
+/// The count is reset when the dealer announces a reshuffle with `DealerRequest::LowCards`.
+/// Counting a continuous shoe is possible, but meaningless.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::counting::{Counter, HiLo};
+/// use twentyone::game::Decision;
+/// use twentyone::prelude::*;
+/// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+/// #     PlayerAction::None
+/// # }
+///
+/// let mut shoe = create_shoe(6);
+/// shuffle_deck(&mut shoe);
+/// let mut dealer = Dealer::new(shoe, DEFAULT_CONFIG, &callback);
+/// dealer.players_mut().push(Player::new(1000));
+/// let mut counter = Counter::new(HiLo, 6);
+///
+/// let mut events = dealer.start_round(true);
+/// loop {
+///     for event in events.iter() {
+///         counter.observe(event, &dealer);
+///     }
+///     let action = match dealer.pending() {
+///         Some(Decision::Bet { .. }) => PlayerAction::Bet(10),
+///         Some(Decision::Play { .. }) => PlayerAction::Stand,
+///         Some(_) => PlayerAction::None,
+///         None => break,
+///     };
+///     events = dealer.apply(action).unwrap();
+/// }
+/// println!("True count: {:.1}", counter.true_count(dealer.shoe()));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Counter<S: CountingSystem> {
+    system: S,
+    decks: u8,
+    running: f32,
+    /// The total of the tags of the cards counted on the table this round
+    table: f32,
+    /// Whether the round was over when the table was last counted
+    finished: bool,
+    /// The amount of cards in the dealer's hand when the table was last counted
+    dealer_cards: usize,
+}
+
+impl<S: CountingSystem> Counter<S> {
+    /// Returns a new Counter for a freshly shuffled shoe
+    ///
+    /// # Arguments
+    ///
+    /// * `system` - The counting system to use
+    /// * `decks` - The amount of decks in the shoe
+    pub fn new(system: S, decks: u8) -> Counter<S> {
+        Counter {
+            running: system.initial_count(decks),
+            system,
+            decks,
+            table: 0.0,
+            finished: false,
+            dealer_cards: 0,
+        }
+    }
+
+    /// Returns a reference to the counting system
+    pub fn system(&self) -> &S {
+        &self.system
+    }
+
+    /// Returns the running count
+    pub fn running_count(&self) -> f32 {
+        self.running
+    }
+
+    /// Returns the running count divided by the amount of decks left in a shoe
+    ///
+    /// # Arguments
+    ///
+    /// * `shoe` - The shoe being counted
+    pub fn true_count(&self, shoe: &Shoe) -> f32 {
+        if shoe.remaining() == 0 {
+            return self.running;
+        }
+        self.running / (shoe.remaining() as f32 / 52.0)
+    }
+
+    /// Count a single card
+    ///
+    /// Cards counted this way are not tracked with the table,
+    /// so they should not be on a table the counter is updated from.
+    ///
+    /// # Arguments
+    ///
+    /// * `card` - The card to count
+    pub fn count(&mut self, card: Card) {
+        self.running += self.system.tag(card.rank);
+    }
+
+    /// Reset the count for a freshly shuffled shoe
+    pub fn reset(&mut self) {
+        self.running = self.system.initial_count(self.decks);
+        self.table = 0.0;
+        self.finished = false;
+        self.dealer_cards = 0;
+    }
+
+    /// Count the cards on the table after an event from the dealer
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The event or request sent by the dealer
+    /// * `dealer` - The dealer that sent it
+    pub fn observe(&mut self, request: &DealerRequest, dealer: &Dealer) {
+        if let DealerRequest::LowCards = request {
+            self.reset();
+        }
+        self.update(dealer);
+    }
+
+    /// Count any cards on the table that haven't been counted yet
+    ///
+    /// # Arguments
+    ///
+    /// * `dealer` - The dealer whose table to count
+    pub fn update(&mut self, dealer: &Dealer) {
+        // A new round has started if the last one was over and its cards were cleared,
+        // even if the counter didn't see the empty table in between
+        let finished = dealer.result().is_some();
+        let new_round = self.finished && !finished && dealer.hand().len() <= self.dealer_cards;
+        self.finished = finished;
+        self.dealer_cards = dealer.hand().len();
+        // The table is cleared between rounds, after its cards have been counted
+        if dealer.hand().is_empty() || new_round {
+            self.table = 0.0;
+        }
+        if dealer.hand().is_empty() {
+            return;
+        }
+
+        let tag = |card: &Card| self.system.tag(card.rank);
+        let players: f32 = dealer
+            .players()
+            .iter()
+            .flat_map(|player| player.hands().iter())
+            .flat_map(|hand| hand.cards().iter())
+            .map(tag)
+            .sum();
+        // The hole card is only shown once the round is over
+        let dealer_cards = if dealer.result().is_some() {
+            dealer.hand().len()
+        } else {
+            1
+        };
+        let table = players
+            + dealer
+                .hand()
+                .iter()
+                .take(dealer_cards)
+                .map(tag)
+                .sum::<f32>();
+
+        self.running += table - self.table;
+        self.table = table;
+    }
+}
@@ -0,0 +1,668 @@
+//! Index plays that deviate from basic strategy at certain true counts
+//!
+//! Deviation tables can be written as text, with one play per line:
+//!
+//! ```text
+//! insurance >= 3
+//! 16 v T S >= 0
+//! TT v 6 P >= 4
+//! 12 v 4 H < 0
+//! A7 v 2 Ds >= 1
+//! ```
+//!
+//! Each play is the player's hand (a hard total, `A` and a card for soft totals,
+//! or a pair), the dealer's up card, the action as written in strategy charts,
+//! and the true counts it is played at. Blank lines and lines starting with `#` are ignored.
+use crate::cards::{Card, Rank};
+use crate::counting::{Counter, CountingSystem};
+use crate::game::{
+    Dealer, GameConfig, HandValue, Player, PlayerAction, PlayerAgent, SurrenderRule,
+};
+use crate::strategy::{Action, Available, BasicStrategy};
+use std::fmt;
+use std::str::FromStr;
+
+/// The kind of hand a deviation applies to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Situation {
+    /// A hard total
+    Hard(u8),
+    /// A soft total
+    Soft(u8),
+    /// A pair of cards with the given value, from 2 to 11 (aces)
+    Pair(u8),
+}
+
+/// The true counts a deviation is played at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Threshold {
+    /// At or above the index
+    AtLeast(f32),
+    /// Below the index
+    Below(f32),
+}
+
+/// A single index play
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Deviation {
+    /// The hand the play applies to
+    pub situation: Situation,
+    /// The value of the dealer's up card, from 2 to 11 (an ace)
+    pub up_card: u8,
+    /// The true counts the play is made at
+    pub threshold: Threshold,
+    /// The action to take instead of basic strategy
+    pub action: Action,
+}
+
+/// A table of index plays, along with when to take insurance
+///
+/// Plays are checked in order, and the first one that applies is used.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::parse_cards;
+/// use twentyone::deviations::Deviations;
+/// use twentyone::game::{PlayerAction, DEFAULT_CONFIG};
+/// use twentyone::strategy::{Available, BasicStrategy};
+///
+/// let strategy = BasicStrategy::new(&DEFAULT_CONFIG);
+/// let deviations = Deviations::illustrious_18(&DEFAULT_CONFIG);
+/// let available = Available {
+///     double: true,
+///     split: true,
+///     surrender: false,
+/// };
+///
+/// // Hard 16 against a ten is hit below a true count of 0, and stood on otherwise
+/// let hand = parse_cards("TS 6H").unwrap();
+/// let up_card = "KD".parse().unwrap();
+/// let action = deviations.action(&strategy, &hand, up_card, -1.0, available);
+/// assert_eq!(action, PlayerAction::Hit);
+/// let action = deviations.action(&strategy, &hand, up_card, 0.0, available);
+/// assert_eq!(action, PlayerAction::Stand);
+///
+/// assert!(deviations.take_insurance(3.0));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Deviations {
+    /// The index plays, in the order they are checked
+    pub plays: Vec<Deviation>,
+    /// The true count at or above which insurance is taken, if it ever is
+    pub insurance: Option<f32>,
+}
+
+/// Reason for a deviation being unable to be parsed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseDeviationError {
+    /// A line did not have the right amount of parts
+    InvalidLength,
+    /// The player's hand was not a total, soft total or pair
+    InvalidHand,
+    /// The dealer's up card was not recognized
+    InvalidUpCard,
+    /// The action was not one of the strategy chart abbreviations
+    InvalidAction,
+    /// The comparison or index was not recognized
+    InvalidThreshold,
+}
+
+impl Threshold {
+    /// Returns whether a true count is within the threshold
+    ///
+    /// # Arguments
+    ///
+    /// * `true_count` - The true count to check
+    pub fn contains(self, true_count: f32) -> bool {
+        match self {
+            Threshold::AtLeast(index) => true_count >= index,
+            Threshold::Below(index) => true_count < index,
+        }
+    }
+}
+
+impl Deviation {
+    /// Returns a deviation played at or above an index
+    ///
+    /// # Arguments
+    ///
+    /// * `situation` - The hand the play applies to
+    /// * `up_card` - The value of the dealer's up card, from 2 to 11
+    /// * `index` - The lowest true count the play is made at
+    /// * `action` - The action to take
+    pub fn at_least(situation: Situation, up_card: u8, index: f32, action: Action) -> Deviation {
+        Deviation {
+            situation,
+            up_card,
+            threshold: Threshold::AtLeast(index),
+            action,
+        }
+    }
+
+    /// Returns a deviation played below an index
+    ///
+    /// # Arguments
+    ///
+    /// * `situation` - The hand the play applies to
+    /// * `up_card` - The value of the dealer's up card, from 2 to 11
+    /// * `index` - The true count the play stops being made at
+    /// * `action` - The action to take
+    pub fn below(situation: Situation, up_card: u8, index: f32, action: Action) -> Deviation {
+        Deviation {
+            situation,
+            up_card,
+            threshold: Threshold::Below(index),
+            action,
+        }
+    }
+}
+
+impl Deviations {
+    /// Returns an empty table, which always plays basic strategy and never takes insurance
+    pub fn new() -> Deviations {
+        Deviations::default()
+    }
+
+    /// Returns the Illustrious 18 Hi-Lo index plays for a game's rules
+    ///
+    /// The indices are for shoe games where the dealer peeks for blackjack,
+    /// adjusted for whether the dealer hits soft 17.
+    /// Hands that basic strategy surrenders are still surrendered when they would be stood on.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rules of the game
+    pub fn illustrious_18(config: &GameConfig) -> Deviations {
+        use Situation::*;
+        let strategy = BasicStrategy::new(config);
+        let h17 = !config.stand_soft_17;
+        // Keep surrendering where basic strategy does
+        let stand = |total, up| match strategy.hard(total, up) {
+            Action::SurrenderOrHit | Action::SurrenderOrStand => Action::SurrenderOrStand,
+            _ => Action::Stand,
+        };
+        // Basic strategy already doubles 11 against an ace in some games
+        let eleven_ace = if strategy.hard(11, 11) == Action::DoubleOrHit {
+            Deviation::below(Hard(11), 11, -1.0, Action::Hit)
+        } else {
+            Deviation::at_least(Hard(11), 11, 1.0, Action::DoubleOrHit)
+        };
+
+        Deviations {
+            plays: vec![
+                Deviation::at_least(Hard(16), 10, 0.0, stand(16, 10)),
+                Deviation::at_least(Hard(15), 10, 4.0, stand(15, 10)),
+                Deviation::at_least(Pair(10), 5, 5.0, Action::Split),
+                Deviation::at_least(Pair(10), 6, 4.0, Action::Split),
+                Deviation::at_least(Hard(10), 10, 4.0, Action::DoubleOrHit),
+                Deviation::at_least(Hard(12), 3, 2.0, Action::Stand),
+                Deviation::at_least(Hard(12), 2, 3.0, Action::Stand),
+                eleven_ace,
+                Deviation::at_least(Hard(9), 2, 1.0, Action::DoubleOrHit),
+                Deviation::at_least(
+                    Hard(10),
+                    11,
+                    if h17 { 3.0 } else { 4.0 },
+                    Action::DoubleOrHit,
+                ),
+                Deviation::at_least(Hard(9), 7, 3.0, Action::DoubleOrHit),
+                Deviation::at_least(Hard(16), 9, 5.0, stand(16, 9)),
+                Deviation::below(Hard(13), 2, -1.0, Action::Hit),
+                Deviation::below(Hard(12), 4, 0.0, Action::Hit),
+                Deviation::below(Hard(12), 5, -2.0, Action::Hit),
+                Deviation::below(Hard(12), 6, -1.0, Action::Hit),
+                Deviation::below(Hard(13), 3, -2.0, Action::Hit),
+            ],
+            insurance: Some(3.0),
+        }
+    }
+
+    /// Returns the Fab 4 Hi-Lo late surrender index plays for a game's rules
+    ///
+    /// Games without late surrender have no plays.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rules of the game
+    pub fn fab_4(config: &GameConfig) -> Deviations {
+        if config.surrender != SurrenderRule::Late {
+            return Deviations::new();
+        }
+        let strategy = BasicStrategy::new(config);
+        // Hands that basic strategy surrenders are hit below the index instead
+        let surrender = |total, up, index| match strategy.hard(total, up) {
+            Action::SurrenderOrHit | Action::SurrenderOrStand => {
+                Deviation::below(Situation::Hard(total), up, index, Action::Hit)
+            }
+            _ => Deviation::at_least(Situation::Hard(total), up, index, Action::SurrenderOrHit),
+        };
+
+        Deviations {
+            plays: vec![
+                surrender(14, 10, 3.0),
+                surrender(15, 10, 0.0),
+                surrender(15, 9, 2.0),
+                surrender(15, 11, if config.stand_soft_17 { 1.0 } else { -1.0 }),
+            ],
+            insurance: None,
+        }
+    }
+
+    /// Add the plays of another table after this table's plays
+    ///
+    /// The other table's insurance index is used if this table doesn't have one.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The table to add
+    pub fn merge(&mut self, other: &Deviations) {
+        self.plays.extend_from_slice(&other.plays);
+        self.insurance = self.insurance.or(other.insurance);
+    }
+
+    /// Returns the action of the first play that applies to a situation, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `situation` - The player's hand
+    /// * `up_card` - The value of the dealer's up card, from 2 to 11
+    /// * `true_count` - The current true count
+    pub fn find(&self, situation: Situation, up_card: u8, true_count: f32) -> Option<Action> {
+        self.plays
+            .iter()
+            .find(|play| {
+                play.situation == situation
+                    && play.up_card == up_card
+                    && play.threshold.contains(true_count)
+            })
+            .map(|play| play.action)
+    }
+
+    /// Returns whether to take insurance at a true count
+    ///
+    /// # Arguments
+    ///
+    /// * `true_count` - The current true count
+    pub fn take_insurance(&self, true_count: f32) -> bool {
+        match self.insurance {
+            Some(index) => true_count >= index,
+            None => false,
+        }
+    }
+
+    /// Returns the action to take with a hand, deviating from basic strategy where the table says
+    ///
+    /// Pairs are checked before totals, as in [`BasicStrategy::action`].
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - The basic strategy to deviate from
+    /// * `hand` - The hand to play
+    /// * `up_card` - The dealer's up card
+    /// * `true_count` - The current true count
+    /// * `available` - The actions that can be taken on the hand
+    pub fn action(
+        &self,
+        strategy: &BasicStrategy,
+        hand: &[Card],
+        up_card: Card,
+        true_count: f32,
+        available: Available,
+    ) -> PlayerAction {
+        let value = HandValue::new(hand);
+        let up = up_card.value();
+
+        if value.is_pair && available.split {
+            let pair = Situation::Pair(hand[0].value());
+            if let Some(action) = self.find(pair, up, true_count) {
+                return action.resolve(available);
+            }
+            // Pairs that basic strategy splits are split before totals are checked
+            if let Action::Split | Action::SurrenderOrSplit = strategy.pair(hand[0].value(), up) {
+                return strategy.action(hand, up_card, available);
+            }
+        }
+
+        let total = if value.soft {
+            Situation::Soft(value.total)
+        } else {
+            Situation::Hard(value.total)
+        };
+        match self.find(total, up, true_count) {
+            Some(action) => action.resolve(available),
+            None => strategy.action(hand, up_card, available),
+        }
+    }
+}
+
+/// Returns the character written for a card value (eg. `T` for 10)
+fn value_char(value: u8) -> char {
+    match value {
+        10 => 'T',
+        11 => 'A',
+        _ => (b'0' + value) as char,
+    }
+}
+
+/// Returns the value of a card written as a rank (eg. `10`, `T` or `K`)
+fn parse_value(s: &str) -> Option<u8> {
+    if s == "10" {
+        return Some(10);
+    }
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Rank::from_char(c).map(Rank::value),
+        _ => None,
+    }
+}
+
+/// Formats a situation as it is written in deviation tables (eg. `16`, `A7` or `TT`)
+impl fmt::Display for Situation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Situation::Hard(total) => write!(f, "{}", total),
+            // The only two-card soft 12 is a pair of aces
+            Situation::Soft(12) => write!(f, "AA"),
+            Situation::Soft(total) => write!(f, "A{}", value_char(total - 11)),
+            Situation::Pair(value) => write!(f, "{0}{0}", value_char(value)),
+        }
+    }
+}
+
+/// Parses a situation from a hard total, `A` and a card for soft totals, or a pair
+impl FromStr for Situation {
+    type Err = ParseDeviationError;
+
+    fn from_str(s: &str) -> Result<Situation, ParseDeviationError> {
+        if let Ok(total) = s.parse::<u8>() {
+            return match total {
+                4..=21 => Ok(Situation::Hard(total)),
+                _ => Err(ParseDeviationError::InvalidHand),
+            };
+        }
+
+        let mut chars = s.chars();
+        let (first, second) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(first), Some(second), None) => (first, second),
+            _ => return Err(ParseDeviationError::InvalidHand),
+        };
+        let value = |c: char| Rank::from_char(c).ok_or(ParseDeviationError::InvalidHand);
+        let (first, second) = (value(first)?, value(second)?);
+        if first.value() == second.value() {
+            Ok(Situation::Pair(first.value()))
+        } else if first == Rank::Ace {
+            Ok(Situation::Soft(11 + second.value()))
+        } else {
+            Err(ParseDeviationError::InvalidHand)
+        }
+    }
+}
+
+/// Formats a threshold as a comparison and an index (eg. `>= 0` or `< -1`)
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Threshold::AtLeast(index) => write!(f, ">= {}", index),
+            Threshold::Below(index) => write!(f, "< {}", index),
+        }
+    }
+}
+
+/// Formats a deviation as a line of a deviation table (eg. `16 v T S >= 0`)
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} v {} {} {}",
+            self.situation,
+            value_char(self.up_card),
+            self.action,
+            self.threshold
+        )
+    }
+}
+
+/// Parses a deviation from a line of a deviation table (eg. `16 v T S >= 0`)
+impl FromStr for Deviation {
+    type Err = ParseDeviationError;
+
+    fn from_str(s: &str) -> Result<Deviation, ParseDeviationError> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let (situation, up_card, action, threshold) = match parts[..] {
+            [situation, "v", up_card, action, comparison, index] => {
+                (situation, up_card, action, (comparison, index))
+            }
+            _ => return Err(ParseDeviationError::InvalidLength),
+        };
+
+        Ok(Deviation {
+            situation: situation.parse()?,
+            up_card: parse_value(up_card).ok_or(ParseDeviationError::InvalidUpCard)?,
+            threshold: parse_threshold(threshold.0, threshold.1)?,
+            action: action
+                .parse()
+                .map_err(|_| ParseDeviationError::InvalidAction)?,
+        })
+    }
+}
+
+/// Parses a threshold from a comparison and an index
+fn parse_threshold(comparison: &str, index: &str) -> Result<Threshold, ParseDeviationError> {
+    let index = index
+        .parse()
+        .map_err(|_| ParseDeviationError::InvalidThreshold)?;
+    match comparison {
+        ">=" => Ok(Threshold::AtLeast(index)),
+        "<" => Ok(Threshold::Below(index)),
+        _ => Err(ParseDeviationError::InvalidThreshold),
+    }
+}
+
+/// Formats a table with the insurance index first, then one play per line
+impl fmt::Display for Deviations {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(index) = self.insurance {
+            writeln!(f, "insurance >= {}", index)?;
+        }
+        for play in self.plays.iter() {
+            writeln!(f, "{}", play)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a table with one play per line, and optionally an `insurance >= <index>` line
+impl FromStr for Deviations {
+    type Err = ParseDeviationError;
+
+    fn from_str(s: &str) -> Result<Deviations, ParseDeviationError> {
+        let mut deviations = Deviations::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts[..] {
+                ["insurance", ">=", index] => {
+                    let index = index
+                        .parse()
+                        .map_err(|_| ParseDeviationError::InvalidThreshold)?;
+                    deviations.insurance = Some(index);
+                }
+                ["insurance", ..] => return Err(ParseDeviationError::InvalidThreshold),
+                _ => deviations.plays.push(line.parse()?),
+            }
+        }
+        Ok(deviations)
+    }
+}
+
+impl fmt::Display for ParseDeviationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseDeviationError::InvalidLength => {
+                write!(
+                    f,
+                    "a play must be written as '<hand> v <up card> <action> <comparison> <index>'"
+                )
+            }
+            ParseDeviationError::InvalidHand => write!(f, "invalid hand"),
+            ParseDeviationError::InvalidUpCard => write!(f, "invalid up card"),
+            ParseDeviationError::InvalidAction => write!(f, "invalid action"),
+            ParseDeviationError::InvalidThreshold => {
+                write!(f, "a threshold must be '>= <index>' or '< <index>'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseDeviationError {}
+
+/// An agent that counts cards and plays basic strategy with index plays, with a flat bet
+///
+/// The agent keeps its own count of every card it sees at the table,
+/// and sits out any round it can't afford the bet for.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::counting::HiLo;
+/// use twentyone::deviations::{Deviations, IndexPlayAgent};
+/// use twentyone::prelude::*;
+/// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+/// #     PlayerAction::None
+/// # }
+///
+/// let mut deviations = Deviations::illustrious_18(&DEFAULT_CONFIG);
+/// deviations.merge(&Deviations::fab_4(&DEFAULT_CONFIG));
+/// let agent = IndexPlayAgent::new(&DEFAULT_CONFIG, HiLo, deviations, 10);
+///
+/// let mut shoe = create_shoe(6);
+/// shuffle_deck(&mut shoe);
+/// let mut dealer = Dealer::new(shoe, DEFAULT_CONFIG, &callback);
+/// dealer.players_mut().push(Player::with_agent(1000, agent));
+/// dealer.play_round(true);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexPlayAgent<S: CountingSystem> {
+    strategy: BasicStrategy,
+    deviations: Deviations,
+    counter: Counter<S>,
+    bet: i32,
+}
+
+impl<S: CountingSystem> IndexPlayAgent<S> {
+    /// Returns a new IndexPlayAgent
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rules of the game to build the basic strategy for
+    /// * `system` - The counting system used for the true count
+    /// * `deviations` - The index plays to make
+    /// * `bet` - The amount to bet every round
+    pub fn new(
+        config: &GameConfig,
+        system: S,
+        deviations: Deviations,
+        bet: i32,
+    ) -> IndexPlayAgent<S> {
+        IndexPlayAgent {
+            strategy: BasicStrategy::new(config),
+            deviations,
+            counter: Counter::new(system, config.shoe_deck_count),
+            bet,
+        }
+    }
+
+    /// Returns a reference to the basic strategy the agent deviates from
+    pub fn strategy(&self) -> &BasicStrategy {
+        &self.strategy
+    }
+
+    /// Returns a reference to the agent's index plays
+    pub fn deviations(&self) -> &Deviations {
+        &self.deviations
+    }
+
+    /// Returns a reference to the agent's count
+    pub fn counter(&self) -> &Counter<S> {
+        &self.counter
+    }
+}
+
+impl<S: CountingSystem> PlayerAgent for IndexPlayAgent<S> {
+    fn bet(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        // Sit out rather than make a bet the dealer would refuse
+        if *player.money() < self.bet {
+            return PlayerAction::None;
+        }
+        PlayerAction::Bet(self.bet)
+    }
+
+    fn play(&mut self, hand: usize, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        let available = Available {
+            double: dealer.can_double(),
+            split: dealer.can_split(),
+            surrender: dealer.can_surrender(),
+        };
+        self.deviations.action(
+            &self.strategy,
+            &player.hands()[hand],
+            dealer.up_card().unwrap(),
+            self.counter.true_count(dealer.shoe()),
+            available,
+        )
+    }
+
+    fn surrender(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        let available = Available {
+            double: true,
+            split: true,
+            surrender: true,
+        };
+        match self.deviations.action(
+            &self.strategy,
+            &player.hands()[0],
+            dealer.up_card().unwrap(),
+            self.counter.true_count(dealer.shoe()),
+            available,
+        ) {
+            PlayerAction::Surrender => PlayerAction::Surrender,
+            _ => PlayerAction::None,
+        }
+    }
+
+    fn insurance(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self.counter.update(dealer);
+        if self
+            .deviations
+            .take_insurance(self.counter.true_count(dealer.shoe()))
+        {
+            // Small bets still take at least 1 of insurance
+            PlayerAction::Insurance((player.hands()[0].wager() / 2).max(1))
+        } else {
+            PlayerAction::None
+        }
+    }
+
+    fn up_card(&mut self, _card: Card, dealer: &Dealer) {
+        self.counter.update(dealer);
+    }
+
+    fn hit_card(&mut self, _card: Card, dealer: &Dealer) {
+        self.counter.update(dealer);
+    }
+
+    fn dealer_hand(&mut self, _hand: &[Card], dealer: &Dealer) {
+        self.counter.update(dealer);
+    }
+
+    fn low_cards(&mut self, dealer: &Dealer) {
+        self.counter.reset();
+        self.counter.update(dealer);
+    }
+}
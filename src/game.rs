@@ -1,18 +1,54 @@
 //! Game-related functions and structures, such as the dealer or hand value checking
-use crate::cards;
+use crate::cards::Card;
+use crate::shoe::{Shoe, ShuffleMode};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use round::Round;
+
+mod agent;
+mod hand;
+mod result;
+mod round;
+mod side_bet;
+mod value;
+
+pub use agent::PlayerAgent;
+pub use hand::Hand;
+pub use result::{HandResult, Outcome, PlayerResult, RoundResult, SideBetResult};
+pub use round::Decision;
+pub use side_bet::{SideBet, SideBetKind, SideBetOutcome};
+pub use value::HandValue;
 
 /// Actions a player can perform
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PlayerAction {
     Hit,
     Stand,
     DoubleDown,
     Split,
+    /// Give up the hand and get back half of the bet
+    Surrender,
+    /// Take insurance against a dealer blackjack
+    ///
+    /// # Arguments
+    ///
+    /// * `i32` - The amount of insurance, up to half of the bet (rounded up).
+    ///   Players with a blackjack take even money with any positive amount.
+    Insurance(i32),
     /// Bet an amount of money
     Bet(i32),
+    /// Place a side bet
+    ///
+    /// # Arguments
+    ///
+    /// * `SideBetKind` - The side bet being offered
+    /// * `i32` - The amount to bet
+    SideBet(SideBetKind, i32),
     None,
 }
 
 /// Requests for the player from the dealer
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DealerRequest {
     /// Request a bet from the player
     Bet,
@@ -23,12 +59,26 @@ pub enum DealerRequest {
     /// * `usize` - The index of the hand to play
     Play(usize),
     /// The dealer's up card
-    UpCard([char; 2]),
+    UpCard(Card),
+    /// Offer early surrender to a player before the dealer peeks for blackjack
+    Surrender,
+    /// Offer insurance to a player when the dealer's up card is an ace
+    ///
+    /// Players with a blackjack are offered even money instead.
+    Insurance,
+    /// Offer a side bet to a player after they have placed their bet
+    ///
+    /// # Arguments
+    ///
+    /// * `SideBetKind` - The side bet being offered
+    SideBet(SideBetKind),
     /// The dealer's hit card
-    HitCard([char; 2]),
+    HitCard(Card),
     /// The dealer's hand after they have finished playing
-    DealerHand(Vec<[char; 2]>),
-    /// The low card threshold was hit and a new shoe was created
+    DealerHand(Vec<Card>),
+    /// The cut card came out or the low card threshold was hit,
+    /// and the shoe was reshuffled before the round.
+    /// Also sent when the shoe runs out and is reshuffled in the middle of a round.
     LowCards,
     /// An error with a returned PlayerAction
     ///
@@ -39,6 +89,7 @@ pub enum DealerRequest {
 }
 
 /// Reason for a dealer being unable to perform an action
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PlayerActionError {
     /// Not enough money for the requested action
     ///
@@ -54,9 +105,46 @@ pub enum PlayerActionError {
     /// * `usize` - The index of the affected hand, if applicable
     /// * `PlayerAction` - The unexpected action
     UnexpectedAction(usize, PlayerAction),
+    /// Surrendering is not allowed, either by the game config or because
+    /// the hand has already been hit or split
+    ///
+    /// # Arguments
+    ///
+    /// * `usize` - The index of the affected hand
+    SurrenderNotAllowed(usize),
+}
+
+/// When players are allowed to surrender
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurrenderRule {
+    /// Surrendering is not allowed
+    None,
+    /// Surrendering is allowed as the first action on a hand,
+    /// but the whole bet is lost if the dealer has blackjack
+    Late,
+    /// Surrendering is allowed as the first action on a hand,
+    /// and half of the bet is returned even if the dealer has blackjack
+    Early,
+}
+
+/// Whether the dealer takes a hole card, and when they check it for blackjack
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HoleCardRule {
+    /// The dealer takes a hole card and peeks for blackjack when showing an ace
+    /// or a ten-value card, ending the round early if they have one
+    Peek,
+    /// European no-hole-card play, where the dealer's second card is dealt
+    /// after all players have finished
+    NoHoleCard {
+        /// Whether only original bets are lost to a dealer blackjack,
+        /// with extra bets from doubling down and splitting returned.
+        /// If `false`, everything is lost.
+        original_bets_only: bool,
+    },
 }
 
 /// Configure different aspects of the game
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct GameConfig {
     /// Whether the dealer should stand on soft 17 or hit
     pub stand_soft_17: bool,
@@ -68,14 +156,38 @@ pub struct GameConfig {
     pub doubling_down: bool,
     /// Whether to allow doubling down after splitting
     pub double_after_split: bool,
+    /// The most hands a player can have by splitting and resplitting
+    pub max_split_hands: usize,
+    /// Whether to allow resplitting aces
+    pub resplit_aces: bool,
+    /// Whether to allow hitting and doubling down on hands from split aces.
+    /// If `false`, split aces receive one card each.
+    pub hit_split_aces: bool,
+    /// Whether an ace and a ten-value card from split aces count as a blackjack
+    /// instead of a regular 21
+    pub split_ace_blackjack: bool,
+    /// When to allow surrendering
+    pub surrender: SurrenderRule,
+    /// Whether to offer insurance (and even money) when the dealer's up card is an ace
+    pub insurance: bool,
+    /// Whether the dealer takes a hole card and peeks for blackjack
+    pub hole_card: HoleCardRule,
     /// The minimum player bet
     pub min_bet: i32,
     /// The maximum player bet
     pub max_bet: i32,
-    /// How many decks to add to the new shoe if `auto_new_shoe` is enabled
+    /// How many decks to put in a new shoe if the shoe runs out of cards
+    /// with nothing in the discard tray to reshuffle
     pub shoe_deck_count: u8,
-    /// How many cards must be left in a deck before DealerRequest::LowCards is called.
-    /// If `auto_new_shoe` is enabled, the new shoe will be created when this number is reached.
+    /// How used cards are returned to the shoe
+    pub shuffle_mode: ShuffleMode,
+    /// The fraction of the shoe dealt before the cut card comes out, from `0.0` to `1.0`
+    pub penetration: f32,
+    /// How many cards to burn after shuffling the shoe
+    pub burn_cards: usize,
+    /// How many cards must be left in the shoe for it to be reshuffled before a round,
+    /// even if the cut card has not come out.
+    /// `DealerRequest::LowCards` is sent whenever the shoe is reshuffled.
     pub low_cards_threshold: usize,
 }
 
@@ -83,8 +195,12 @@ pub struct GameConfig {
 ///
 /// Allows doubling down and splitting, stands on soft 17,
 /// pays out blackjacks 3 to 2, and allows doubling after splitting.
+/// Allows resplitting up to 4 hands, except for aces, which receive one card each.
+/// Offers insurance, and does not allow surrendering.
+/// The dealer peeks for blackjack when showing an ace or a ten-value card.
 ///
-/// Creates a new 6-deck shoe when 52 or less cards are remaining.
+/// Reshuffles the shoe before a round once 75% of it has been dealt
+/// or 52 or less cards are remaining, then burns one card.
 /// Minimum bet is 1 and maximum bet is `i32::MAX` (2,147,483,647)
 pub const DEFAULT_CONFIG: GameConfig = GameConfig {
     stand_soft_17: true,
@@ -92,34 +208,64 @@ pub const DEFAULT_CONFIG: GameConfig = GameConfig {
     splitting: true,
     doubling_down: true,
     double_after_split: true,
+    max_split_hands: 4,
+    resplit_aces: false,
+    hit_split_aces: false,
+    split_ace_blackjack: false,
+    surrender: SurrenderRule::None,
+    insurance: true,
+    hole_card: HoleCardRule::Peek,
     min_bet: 1,
     max_bet: i32::MAX,
     shoe_deck_count: 6,
+    shuffle_mode: ShuffleMode::CutCard,
+    penetration: 0.75,
+    burn_cards: 1,
     low_cards_threshold: 52,
 };
 
 /// Describes a blackjack dealer
 pub struct Dealer<'a> {
-    hand: Vec<[char; 2]>,
-    shoe: Vec<[char; 2]>,
+    hand: Vec<Card>,
+    shoe: Shoe,
     players: Vec<Player>,
     config: GameConfig,
-    callback: &'a dyn Fn(DealerRequest, Option<&Player>, &Dealer) -> PlayerAction,
+    callback: Option<Box<dyn PlayerAgent + 'a>>,
+    side_bets: Vec<Box<dyn SideBet + 'a>>,
+    rng: Box<dyn RngCore + 'a>,
+    round: Round,
+    rigged: Option<Rigged>,
+    /// Whether the shoe ran out and was reshuffled since the last events were sent
+    reshuffled: bool,
+}
+
+/// Cards chosen with [`Dealer::rig`] for the next deal
+struct Rigged {
+    players: Vec<[Card; 2]>,
+    dealer: [Card; 2],
+    hits: Vec<Card>,
 }
 
 /// Describes a blackjack player
 pub struct Player {
     money: i32,
-    hands: Vec<Vec<[char; 2]>>,
+    hands: Vec<Hand>,
+    agent: Option<Box<dyn PlayerAgent>>,
 }
 
-impl Dealer<'_> {
+impl<'a> Dealer<'a> {
     /// Returns a new Dealer
     ///
     /// # Arguments
     ///
-    /// * `shoe` - The shoe (or deck) to draw from
-    /// * `callback` - A function to handle player turns
+    /// * `shoe` - The shoe (or deck) to draw from, which may be a `Shoe` or a `Vec<Card>`
+    /// * `callback` - A function or `PlayerAgent` to handle player turns
+    ///
+    /// The shoe's shuffling mode and cut card are set using the config.
+    ///
+    /// `callback` handles the turns of every player without their own agent
+    /// (see [`Player::with_agent`]), and is sent all dealer updates.
+    /// Since it may be any `PlayerAgent`, it can be a closure that keeps its own state.
     ///
     /// `callback` is passed a `DealerRequest` and an `Option<&Player>`.
     /// The option will always have a player if it applies to the event (eg. betting),
@@ -130,14 +276,17 @@ impl Dealer<'_> {
     /// The callback function will always return a `PlayerAction`,
     /// but it should return different things based on the `DealerRequest`:
     ///
-    /// | `DealerRequest`                             | `PlayerAction`                                                                                       |
-    /// |---------------------------------------------|------------------------------------------------------------------------------------------------------|
-    /// | `DealerRequest::Bet`                        | `PlayerAction::Bet(i32)`                                                                             |
-    /// | `DealerRequest::Play`                       | One of `PlayerAction::Hit`, `PlayerAction::Stand`, `PlayerAction::DoubleDown`, `PlayerAction::Split` |
-    /// | `DealerRequest::Error(PlayerActionError)`   | `PlayerAction::None` and handle the returned error                                                   |
-    /// | `DealerRequest::UpCard([char; 2])`          | `PlayerAction::None`                                                                                 |
-    /// | `DealerRequest::HitCard([char; 2])`         | `PlayerAction::None`                                                                                 |
-    /// | `DealerRequest::DealerHand(Vec<[char; 2]>)` | `PlayerAction::None`                                                                                 |
+    /// | `DealerRequest`                           | `PlayerAction`                                                                                                                  |
+    /// |-------------------------------------------|---------------------------------------------------------------------------------------------------------------------------------|
+    /// | `DealerRequest::Bet`                      | `PlayerAction::Bet(i32)`, or `PlayerAction::None` to sit out the round                                                          |
+    /// | `DealerRequest::Play`                     | One of `PlayerAction::Hit`, `PlayerAction::Stand`, `PlayerAction::DoubleDown`, `PlayerAction::Split`, `PlayerAction::Surrender` |
+    /// | `DealerRequest::Surrender`                | `PlayerAction::Surrender`, or `PlayerAction::None` to decline                                                                   |
+    /// | `DealerRequest::Insurance`                | `PlayerAction::Insurance(i32)` to take insurance (or even money), or `PlayerAction::None` to decline                            |
+    /// | `DealerRequest::SideBet(SideBetKind)`     | `PlayerAction::SideBet(SideBetKind, i32)` with the offered kind, or `PlayerAction::None` to decline                             |
+    /// | `DealerRequest::Error(PlayerActionError)` | `PlayerAction::None` and handle the returned error                                                                              |
+    /// | `DealerRequest::UpCard(Card)`             | `PlayerAction::None`                                                                                                            |
+    /// | `DealerRequest::HitCard(Card)`            | `PlayerAction::None`                                                                                                            |
+    /// | `DealerRequest::DealerHand(Vec<Card>)`    | `PlayerAction::None`                                                                                                            |
     ///
     /// If an unexpected return value is given, the callback will be called
     ///  again with a request of `DealerAction::Error(PlayerActionError::UnexpectedAction)`
@@ -150,27 +299,40 @@ impl Dealer<'_> {
     /// # Examples
     ///
     /// Example code is available in the [Quick Start](../index.html#quick-start) from the main page.
-    pub fn new<'a>(
-        shoe: Vec<[char; 2]>,
+    pub fn new<S: Into<Shoe>, A: PlayerAgent + 'a>(
+        shoe: S,
         game_config: GameConfig,
-        callback: &'a dyn Fn(DealerRequest, Option<&Player>, &Dealer) -> PlayerAction,
-    ) -> Dealer {
+        callback: A,
+    ) -> Dealer<'a> {
+        let mut shoe = shoe.into();
+        shoe.set_mode(game_config.shuffle_mode);
+        shoe.set_penetration(game_config.penetration);
         Dealer {
             hand: Vec::new(),
-            shoe: shoe,
+            shoe,
             players: Vec::new(),
             config: game_config,
-            callback: callback,
+            callback: Some(Box::new(callback)),
+            side_bets: Vec::new(),
+            rng: Box::new(StdRng::from_entropy()),
+            round: Round::new(),
+            rigged: None,
+            reshuffled: false,
         }
     }
 
     /// Returns a reference to the dealer's hand
-    pub fn hand(&self) -> &Vec<[char; 2]> {
+    pub fn hand(&self) -> &Vec<Card> {
         &self.hand
     }
 
+    /// Returns the dealer's up card, if they have been dealt one
+    pub fn up_card(&self) -> Option<Card> {
+        self.hand.first().copied()
+    }
+
     /// Returns a reference to the dealer's shoe
-    pub fn shoe(&self) -> &Vec<[char; 2]> {
+    pub fn shoe(&self) -> &Shoe {
         &self.shoe
     }
 
@@ -178,13 +340,69 @@ impl Dealer<'_> {
     pub fn players(&self) -> &Vec<Player> {
         &self.players
     }
+
+    /// Returns a reference to the game's rules
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Returns the side bets offered at the table
+    pub fn side_bets(&self) -> &[Box<dyn SideBet + 'a>] {
+        &self.side_bets
+    }
+
+    /// Returns whether a kind of side bet is offered at the table
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of side bet
+    pub fn offers_side_bet(&self, kind: SideBetKind) -> bool {
+        self.side_bets
+            .iter()
+            .any(|side_bet| side_bet.kind() == kind)
+    }
+
+    /// Offer a side bet to every player, starting with the next round
+    ///
+    /// Side bets are offered in the order they were added.
+    /// A side bet of a kind that is already offered replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `side_bet` - The side bet to offer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::game::SideBetKind;
+    /// use twentyone::prelude::*;
+    /// use twentyone::side_bets::PerfectPairs;
+    /// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+    /// #     PlayerAction::None
+    /// # }
+    /// let mut dealer = Dealer::new(Shoe::new(6), DEFAULT_CONFIG, &callback);
+    /// dealer.add_side_bet(PerfectPairs::new());
+    /// assert!(dealer.offers_side_bet(SideBetKind::PerfectPairs));
+    /// ```
+    pub fn add_side_bet<B: SideBet + 'a>(&mut self, side_bet: B) {
+        let kind = side_bet.kind();
+        match self
+            .side_bets
+            .iter()
+            .position(|offered| offered.kind() == kind)
+        {
+            Some(i) => self.side_bets[i] = Box::new(side_bet),
+            None => self.side_bets.push(Box::new(side_bet)),
+        }
+    }
+
     /// Returns a mutable reference to the dealer's hand
-    pub fn hand_mut(&mut self) -> &mut Vec<[char; 2]> {
+    pub fn hand_mut(&mut self) -> &mut Vec<Card> {
         &mut self.hand
     }
 
     /// Returns a mutable reference to the dealer's shoe
-    pub fn shoe_mut(&mut self) -> &mut Vec<[char; 2]> {
+    pub fn shoe_mut(&mut self) -> &mut Shoe {
         &mut self.shoe
     }
 
@@ -193,21 +411,126 @@ impl Dealer<'_> {
         &mut self.players
     }
 
-    /// Clear the dealer's and all players' hands
+    /// Returns a mutable reference to the dealer's random number generator
+    pub fn rng_mut(&mut self) -> &mut dyn RngCore {
+        &mut self.rng
+    }
+
+    /// Replace the random number generator used to shuffle new shoes
+    ///
+    /// By default, the dealer uses a generator seeded from system entropy.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The random number generator to use
+    pub fn set_rng<R: RngCore + 'a>(&mut self, rng: R) {
+        self.rng = Box::new(rng);
+    }
+
+    /// Seed the random number generator used to shuffle new shoes
+    ///
+    /// Dealers with the same seed, shoe, players and actions
+    /// will always play out the same sequence of rounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed to use
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::prelude::*;
+    /// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+    /// #     PlayerAction::None
+    /// # }
+    /// let mut shoe = create_shoe(6);
+    /// twentyone::cards::shuffle_deck_seeded(&mut shoe, 21);
+    /// let mut dealer = Dealer::new(shoe, DEFAULT_CONFIG, &callback);
+    /// dealer.set_seed(21);
+    /// ```
+    pub fn set_seed(&mut self, seed: u64) {
+        self.set_rng(StdRng::seed_from_u64(seed));
+    }
+
+    /// Choose the cards dealt in the next round, for testing specific scenarios
+    ///
+    /// The cards are put on top of the shoe once every player has bet,
+    /// after any reshuffle (see [`Shoe::stack`]).
+    /// There must be a pair of cards for every player at the table.
+    /// The pairs of players who sit out the round are not dealt.
+    ///
+    /// # Arguments
+    ///
+    /// * `players` - The two cards dealt to each player, in the same order as the players
+    /// * `dealer` - The dealer's up card and hole card
+    /// * `hits` - The cards drawn after the deal, in order (eg. player hits and dealer hits).
+    ///   In no-hole-card games, the dealer's second card is drawn after these.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::cards::parse_cards;
+    /// use twentyone::prelude::*;
+    /// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+    /// #     PlayerAction::None
+    /// # }
+    /// let mut dealer = Dealer::new(Shoe::new(6), DEFAULT_CONFIG, &callback);
+    /// dealer.players_mut().push(Player::new(1000));
+    ///
+    /// // Deal a pair of eights against a dealer six, then a three and a king to the split hands
+    /// let eights = ["8D".parse().unwrap(), "8C".parse().unwrap()];
+    /// let dealer_cards = ["6S".parse().unwrap(), "TH".parse().unwrap()];
+    /// dealer.rig(&[eights], dealer_cards, &parse_cards("3H KS").unwrap());
+    ///
+    /// dealer.start_round(true);
+    /// dealer.apply(PlayerAction::Bet(10)).unwrap();
+    /// dealer.apply(PlayerAction::Split).unwrap();
+    /// assert_eq!(dealer.players()[0].hands()[0].cards(), &parse_cards("8D 3H").unwrap());
+    /// assert_eq!(dealer.players()[0].hands()[1].cards(), &parse_cards("8C KS").unwrap());
+    /// ```
+    pub fn rig(&mut self, players: &[[Card; 2]], dealer: [Card; 2], hits: &[Card]) {
+        self.rigged = Some(Rigged {
+            players: players.to_vec(),
+            dealer,
+            hits: hits.to_vec(),
+        });
+    }
+
+    /// Clear the dealer's and all players' hands, putting the cards in the discard tray
     pub fn clear_table(&mut self) {
-        self.hand.clear();
+        self.shoe.discard(self.hand.drain(..));
         for player in self.players.iter_mut() {
-            player.hands_mut().clear();
-            player.hands_mut().push(Vec::new());
+            for hand in player.hands.drain(..) {
+                self.shoe.discard(hand.cards);
+            }
+            player.hands.push(Hand::default());
         }
     }
 
-    /// Deal a hand to all players
+    /// Shuffle the discard tray back into the shoe and burn cards
+    ///
+    /// Cards still on the table are not shuffled back in.
+    pub fn shuffle_shoe(&mut self) {
+        self.shoe.shuffle(&mut self.rng);
+        self.shoe.burn(self.config.burn_cards);
+    }
+
+    /// Deal a hand to all players, except those sitting out the round
+    ///
+    /// Each player is dealt a card, followed by the dealer's up card,
+    /// then each player's second card and the dealer's hole card.
+    /// In no-hole-card games, the dealer is only dealt their up card.
     pub fn deal_hands(&mut self) {
-        for _ in 0..2 {
-            cards::hit_card(&mut self.shoe, &mut self.hand);
-            for player in self.players.iter_mut() {
-                cards::hit_card(&mut self.shoe, &mut player.hands_mut()[0]);
+        for i in 0..2 {
+            for player in 0..self.players.len() {
+                // Players sitting out have no hand to deal to
+                if !self.players[player].hands.is_empty() {
+                    self.hit_card(player, 0);
+                }
+            }
+            if i == 0 || self.config.hole_card == HoleCardRule::Peek {
+                let card = self.draw_card();
+                self.hand.push(card);
             }
         }
     }
@@ -219,246 +542,98 @@ impl Dealer<'_> {
     /// * `player` - The index of the player to hit
     /// * `hand` - The index of the player's hand (used for split hands)
     pub fn hit_card(&mut self, player: usize, hand: usize) {
-        cards::hit_card(&mut self.shoe, &mut self.players[player].hands[hand]);
+        let card = self.draw_card();
+        self.players[player].hands[hand].cards.push(card);
+    }
+
+    /// Draw a card from the shoe
+    ///
+    /// If the shoe runs out in the middle of a round, the discard tray is shuffled back in.
+    /// If there is nothing to shuffle back in, a new shoe is created.
+    /// The reshuffle is announced with the next events the dealer sends.
+    fn draw_card(&mut self) -> Card {
+        if self.shoe.remaining() == 0 {
+            if self.shoe.discards().is_empty() {
+                self.shoe = Shoe::new(self.config.shoe_deck_count);
+                self.shoe.set_mode(self.config.shuffle_mode);
+                self.shoe.set_penetration(self.config.penetration);
+            }
+            self.shuffle_shoe();
+            self.reshuffled = true;
+        }
+        self.shoe.draw().unwrap()
     }
 
     /// Play a round of blackjack
     ///
-    /// Calls `callback` to get player bets/actions.
+    /// Asks each player's agent, or `callback` for players without one, for bets/actions.
+    /// This is a loop over [`start_round`](Dealer::start_round),
+    /// [`pending`](Dealer::pending) and [`apply`](Dealer::apply),
+    /// which can be used directly instead of a callback.
+    ///
+    /// Returns the result of the round, with the outcome of every player's hands.
     ///
     /// # Arguments
     ///
     /// * `clear_table` - Clear the table at the beginning of the round
-    pub fn play_round(&mut self, clear_table: bool) {
-        if clear_table {
-            self.clear_table();
-        }
-
-        let mut player_bets: Vec<i32> = Vec::new();
-
-        // Get bets
-        for i in 0..self.players.len() {
-            loop {
-                let bet = (self.callback)(DealerRequest::Bet, Some(&self.players[i]), &self);
-                if let PlayerAction::Bet(amount) = bet {
-                    // Check if player can afford bet and if it is within limits
-                    if self.players[i].money() >= &amount {
-                        if self.config.min_bet <= amount && amount <= self.config.max_bet {
-                            player_bets.push(amount);
-                            *self.players[i].money_mut() -= amount;
-                            break;
-                        } else {
-                            let error = PlayerActionError::UnexpectedAction(0, bet);
-                            (self.callback)(
-                                DealerRequest::Error(error),
-                                Some(&self.players[i]),
-                                &self,
-                            );
-                        }
-                    } else {
-                        let error = PlayerActionError::NotEnoughMoney(0, bet);
-                        (self.callback)(DealerRequest::Error(error), Some(&self.players[i]), &self);
-                    }
-                } else {
-                    let error = PlayerActionError::UnexpectedAction(0, bet);
-                    (self.callback)(DealerRequest::Error(error), Some(&self.players[i]), &self);
+    pub fn play_round(&mut self, clear_table: bool) -> RoundResult {
+        let events = self.start_round(clear_table);
+        self.notify(events);
+
+        while let Some(decision) = self.pending() {
+            let player = decision.player();
+            let action = self.ask(player, decision.request());
+            match self.apply(action) {
+                Ok(events) => self.notify(events),
+                Err(error) => {
+                    self.ask(player, DealerRequest::Error(error));
                 }
             }
         }
 
-        // Deal hands
-        self.deal_hands();
-
-        // Send dealer up card
-        (self.callback)(DealerRequest::UpCard(self.hand[1]), None, &self);
-
-        // Get player actions
-        for i in 0..self.players.len() {
-            let mut can_double: Vec<bool>;
-            let mut can_split: bool;
-            if self.config.doubling_down {
-                // Check if player has enough money to double down
-                can_double = vec![self.players[i].money() >= &player_bets[i]];
-            } else {
-                can_double = vec![false];
-            }
-            if self.config.splitting {
-                // Check if player cards are valid for a split and if player has enough money
-                can_split = crate::game::can_split(&self.players[i].hands()[0]) && can_double[0];
-            } else {
-                can_split = false;
-            }
-
-            // Keep track of stood hands
-            let mut stood = vec![false];
-
-            // Keep track of original bet
-            // Used when doubling after splitting
-            let original_bet = player_bets[i];
-
-            // Active hand
-            let mut hand_count = 1;
-            let mut j = 0;
-            // Get actions from each hand, one at a time
-            // Using a loop and incrementing j manually because for loops would not recheck
-            // length of player.hands() after a split
-            loop {
-                // Check for low cards
-                if self.shoe.len() <= self.config.low_cards_threshold {
-                    (self.callback)(DealerRequest::LowCards, None, &self);
-                    // Create a new shoe if the option is enabled
-                    self.shoe = cards::create_shoe(self.config.shoe_deck_count);
-                    cards::shuffle_deck(&mut self.shoe);
-                }
+        // The round only stops waiting on decisions once it has been paid out
+        self.result().cloned().unwrap()
+    }
 
-                if j >= hand_count {
-                    break;
-                }
-                while !stood[j] {
-                    let action =
-                        (self.callback)(DealerRequest::Play(j), Some(&self.players[i]), &self);
-                    match action {
-                        PlayerAction::Hit => {
-                            self.hit_card(i, j);
-                            can_double[j] = false;
-                        }
-                        PlayerAction::Stand => stood[j] = true,
-                        PlayerAction::DoubleDown => {
-                            if can_double[j] {
-                                *self.players[i].money_mut() -= original_bet;
-                                player_bets[i] += original_bet;
-                                stood[j] = true;
-                                self.hit_card(i, j);
-                                can_double[j] = false;
-                            } else {
-                                (self.callback)(
-                                    DealerRequest::Error(PlayerActionError::UnexpectedAction(
-                                        j, action,
-                                    )),
-                                    Some(&self.players[i]),
-                                    &self,
-                                );
-                            }
-                        }
-                        PlayerAction::Split => {
-                            if can_split {
-                                *self.players[i].money_mut() -= original_bet;
-                                player_bets[i] += original_bet;
-                                self.players[i].hands_mut().push(Vec::new());
-                                stood.push(false);
-                                if self.config.double_after_split && self.config.doubling_down {
-                                    can_double.push(true);
-                                } else {
-                                    can_double[0] = false;
-                                    can_double.push(false);
-                                }
-                                // "Draw" card from first hand and place it into second
-                                let card = cards::draw_card(
-                                    self.players[i].hands_mut().get_mut(0).unwrap(),
-                                );
-                                self.players[i].hands_mut()[1].push(card.unwrap());
-                                // Hit another card to each hand
-                                self.hit_card(i, 0);
-                                self.hit_card(i, 1);
-                                hand_count = 2;
-                                can_split = false;
-                            } else {
-                                (self.callback)(
-                                    DealerRequest::Error(PlayerActionError::UnexpectedAction(
-                                        j, action,
-                                    )),
-                                    Some(&self.players[i]),
-                                    &self,
-                                );
-                            }
-                        }
-                        _ => {
-                            let error = PlayerActionError::UnexpectedAction(j, action);
-                            (self.callback)(
-                                DealerRequest::Error(error),
-                                Some(&self.players[i]),
-                                &self,
-                            );
-                        }
-                    }
-
-                    // Check if the hand is busted
-                    if get_hand_value(&self.players[i].hands()[j], true) > 21 {
-                        stood[j] = true;
-                    }
-                }
-                j += 1;
+    /// Send a request to a player's agent, or to the callback if the player has none
+    fn ask(&mut self, player: usize, request: DealerRequest) -> PlayerAction {
+        // Agents are taken out while they are asked so that they can be borrowed mutably
+        // alongside the dealer
+        match self.players[player].agent.take() {
+            Some(mut agent) => {
+                let action =
+                    agent::send(agent.as_mut(), request, Some(&self.players[player]), self);
+                self.players[player].agent = Some(agent);
+                action
             }
-        }
-
-        // Dealer play
-        let mut busted = false;
-        loop {
-            let hand_value = get_hand_value(&self.hand, true);
-            if hand_value > 21 {
-                busted = true;
-                break;
-            } else if hand_value >= 17 {
-                if self.config.stand_soft_17 {
-                    break;
-                // Check if hand is exactly 17 contains an ace
-                } else if hand_value == 17 && self.hand.iter().any(|&i| i[1] == 'A') {
-                    // Check if ace is acting as an 11 or a 1
-                    if hand_value == get_hand_value(&self.hand, false) {
-                        let card = cards::draw_card(&mut self.shoe).unwrap();
-                        self.hand.push(card);
-                        (self.callback)(DealerRequest::HitCard(card), None, &self);
-                    } else {
-                        break;
-                    }
-                }
-                break;
-            } else {
-                let card = cards::draw_card(&mut self.shoe).unwrap();
-                self.hand.push(card);
-                (self.callback)(DealerRequest::HitCard(card), None, &self);
+            None => {
+                let mut callback = self.callback.take().unwrap();
+                let action = agent::send(
+                    callback.as_mut(),
+                    request,
+                    Some(&self.players[player]),
+                    self,
+                );
+                self.callback = Some(callback);
+                action
             }
         }
+    }
 
-        // Pay out winners
-        let dealer_hand_value = get_hand_value(&self.hand, true);
-        for i in 0..self.players.len() {
-            for j in 0..self.players[i].hands().len() {
-                let hand_value = get_hand_value(&self.players[i].hands()[j], true);
-                // Check if player busted
-                if hand_value > 21 {
-                    continue;
-                }
-
-                // Pay out normal amount if player did not bust, did not have blackjack,
-                // and beat dealer/dealer busted
-                if hand_value < 21 && (busted || hand_value > dealer_hand_value) {
-                    self.players[i].money +=
-                        player_bets[i] * 2 / self.players[i].hands().len() as i32;
-                } else if hand_value == 21 && (busted || hand_value > dealer_hand_value) {
-                    // Check if player had blackjack
-                    if self.players[i].hands()[j].len() == 2 {
-                        // Make sure dealer didn't have blackjack
-                        if dealer_hand_value == 21 && self.hand.len() == 2 {
-                            // Push, refund player
-                            self.players[i].money += player_bets[i];
-                        } else {
-                            // Pay out 3 to 2
-                            self.players[i].money += player_bets[i]
-                                + (player_bets[i] as f32 * self.config.blackjack_payout) as i32;
-                        }
-                    } else {
-                        self.players[i].money +=
-                            player_bets[i] * 2 / self.players[i].hands().len() as i32;
-                    }
-                // Push, refund player
-                } else if hand_value == dealer_hand_value {
-                    self.players[i].money += player_bets[i];
+    /// Send events that do not apply to a player to the callback and all players' agents
+    fn notify(&mut self, events: Vec<DealerRequest>) {
+        for event in events {
+            let mut callback = self.callback.take().unwrap();
+            agent::send(callback.as_mut(), event.clone(), None, self);
+            self.callback = Some(callback);
+
+            for i in 0..self.players.len() {
+                if let Some(mut agent) = self.players[i].agent.take() {
+                    agent::send(agent.as_mut(), event.clone(), None, self);
+                    self.players[i].agent = Some(agent);
                 }
             }
         }
-
-        (self.callback)(DealerRequest::DealerHand(self.hand.clone()), None, &self);
     }
 }
 
@@ -468,7 +643,6 @@ impl Player {
     /// # Arguments
     ///
     /// * `money` - The amount of money to give the player
-    /// * `hands` - A Vector of hands (`Vec<[char; 2]>`)
     ///
     /// # Examples
     ///
@@ -478,18 +652,78 @@ impl Player {
     /// ```
     pub fn new(money: i32) -> Player {
         Player {
-            money: money,
-            hands: vec![Vec::new()],
+            money,
+            hands: vec![Hand::default()],
+            agent: None,
         }
     }
 
+    /// Returns a new Player with its own agent
+    ///
+    /// The agent is asked for the player's bets/actions instead of the dealer's callback,
+    /// and is sent all dealer updates.
+    ///
+    /// Unlike the dealer's callback, the agent must be `'static`, since `Player` has no
+    /// lifetime parameter and can be moved between dealers.
+    /// Closures should take ownership of their state (eg. with `move`), and shared state
+    /// can be kept in an `Rc` or `Arc`.
+    ///
+    /// # Arguments
+    ///
+    /// * `money` - The amount of money to give the player
+    /// * `agent` - The agent that makes the player's decisions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::game::{Dealer, DealerRequest, Player, PlayerAction};
+    ///
+    /// // Closures may keep their own state
+    /// let mut rounds = 0;
+    /// let player = Player::with_agent(
+    ///     100,
+    ///     move |request: DealerRequest, _: Option<&Player>, _: &Dealer| match request {
+    ///         DealerRequest::Bet => {
+    ///             rounds += 1;
+    ///             PlayerAction::Bet(rounds)
+    ///         }
+    ///         _ => PlayerAction::Stand,
+    ///     },
+    /// );
+    /// ```
+    pub fn with_agent<A: PlayerAgent + 'static>(money: i32, agent: A) -> Player {
+        Player {
+            money,
+            hands: vec![Hand::default()],
+            agent: Some(Box::new(agent)),
+        }
+    }
+
+    /// Replace the player's agent
+    ///
+    /// The agent must be `'static`, as with [`with_agent`](Player::with_agent).
+    ///
+    /// # Arguments
+    ///
+    /// * `agent` - The agent that makes the player's decisions
+    pub fn set_agent<A: PlayerAgent + 'static>(&mut self, agent: A) {
+        self.agent = Some(Box::new(agent));
+    }
+
+    /// Remove and return the player's agent
+    ///
+    /// The player's decisions will be made by the dealer's callback afterwards.
+    pub fn take_agent(&mut self) -> Option<Box<dyn PlayerAgent>> {
+        self.agent.take()
+    }
+
     /// Returns a reference to the player's money
     pub fn money(&self) -> &i32 {
         &self.money
     }
 
     /// Returns a reference to the player's hands
-    pub fn hands(&self) -> &Vec<Vec<[char; 2]>> {
+    pub fn hands(&self) -> &Vec<Hand> {
         &self.hands
     }
 
@@ -499,16 +733,20 @@ impl Player {
     }
 
     /// Returns a mutable reference to the player's hands
-    pub fn hands_mut(&mut self) -> &mut Vec<Vec<[char; 2]>> {
+    pub fn hands_mut(&mut self) -> &mut Vec<Hand> {
         &mut self.hands
     }
 }
 
 /// Returns the value of a hand
 ///
+/// Use [`HandValue`] to also tell whether the hand is soft, a blackjack, busted or a pair.
+///
 /// # Arguments
 ///
 /// * `hand` - The hand to get the value of
+/// * `auto_aces` - Count aces as 1 where counting them as 11 would bust the hand.
+///   If `false`, every ace is counted as 11.
 ///
 /// # Examples
 ///
@@ -521,41 +759,12 @@ impl Player {
 /// cards::hit_card(&mut deck, &mut hand);
 /// println!("{}", game::get_hand_value(&hand, true));
 /// ```
-pub fn get_hand_value(hand: &Vec<[char; 2]>, auto_aces: bool) -> u8 {
-    let mut value = 0;
-    let mut aces = 0;
-    for i in hand.iter() {
-        value += match i[1] {
-            '2' => 2,
-            '3' => 3,
-            '4' => 4,
-            '5' => 5,
-            '6' => 6,
-            '7' => 7,
-            '8' => 8,
-            '9' => 9,
-            'T' | 'J' | 'Q' | 'K' => 10,
-            'A' => {
-                aces += 1;
-                0
-            }
-            _ => 0,
-        }
-    }
-    // Add aces
+pub fn get_hand_value(hand: &[Card], auto_aces: bool) -> u8 {
     if auto_aces {
-        // Check if an ace being 11 would bust the hand
-        for _ in 0..aces {
-            if value + 11 > 21 {
-                value += 1;
-            } else {
-                value += 11;
-            }
-        }
+        HandValue::new(hand).total
     } else {
-        value += 11 * aces;
+        hand.iter().map(|card| card.value()).sum()
     }
-    value
 }
 
 /// Returns whether a hand is able to split
@@ -575,10 +784,82 @@ pub fn get_hand_value(hand: &Vec<[char; 2]>, auto_aces: bool) -> u8 {
 /// cards::hit_card(&mut deck, &mut hand);
 /// println!("{}", game::can_split(&hand));
 /// ```
-pub fn can_split(hand: &Vec<[char; 2]>) -> bool {
-    if hand.len() != 2 {
-        return false;
-    }
+pub fn can_split(hand: &[Card]) -> bool {
+    HandValue::new(hand).is_pair
+}
+
+/// Returns whether a dealer must hit a hand under the game's rules
+///
+/// The dealer hits below 17, and hits soft 17 (a 17 with an ace counted as 11)
+/// unless `stand_soft_17` is set.
+///
+/// # Arguments
+///
+/// * `hand` - The dealer's hand
+/// * `rules` - The rules of the game
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::parse_cards;
+/// use twentyone::game::{self, GameConfig, DEFAULT_CONFIG};
+/// let soft_17 = parse_cards("AS 6H").unwrap();
+/// let h17 = GameConfig {
+///     stand_soft_17: false,
+///     ..DEFAULT_CONFIG
+/// };
+/// assert!(!game::dealer_hits(&soft_17, &DEFAULT_CONFIG));
+/// assert!(game::dealer_hits(&soft_17, &h17));
+/// ```
+pub fn dealer_hits(hand: &[Card], rules: &GameConfig) -> bool {
+    let value = HandValue::new(hand);
+    value.total < 17 || (value.total == 17 && value.soft && !rules.stand_soft_17)
+}
 
-    hand[0][1] == hand[1][1]
+/// Play out a dealer's hand under the game's rules, drawing cards from a shoe
+///
+/// Cards are drawn until the dealer must stand (see [`dealer_hits`]) or the shoe runs out.
+/// Hands with one card (eg. in no-hole-card games) are dealt their second card first.
+///
+/// Returns the cards that were drawn, in order.
+///
+/// # Arguments
+///
+/// * `hand` - The dealer's hand
+/// * `shoe` - The shoe to draw from
+/// * `rules` - The rules of the game
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::parse_cards;
+/// use twentyone::game::{self, DEFAULT_CONFIG};
+/// use twentyone::shoe::Shoe;
+/// let mut hand = parse_cards("TS 4H").unwrap();
+/// let mut shoe: Shoe = "2D 5C 9H".parse().unwrap();
+/// let drawn = game::dealer_play(&mut hand, &mut shoe, &DEFAULT_CONFIG);
+/// assert_eq!(drawn, parse_cards("2D 5C").unwrap());
+/// assert_eq!(game::get_hand_value(&hand, true), 21);
+/// ```
+pub fn dealer_play(hand: &mut Vec<Card>, shoe: &mut Shoe, rules: &GameConfig) -> Vec<Card> {
+    play_dealer_hand(hand, rules, || shoe.draw())
+}
+
+/// Play out a dealer's hand, drawing cards with a function until it returns `None`
+fn play_dealer_hand<F: FnMut() -> Option<Card>>(
+    hand: &mut Vec<Card>,
+    rules: &GameConfig,
+    mut draw: F,
+) -> Vec<Card> {
+    let mut drawn = Vec::new();
+    while hand.len() < 2 || dealer_hits(hand, rules) {
+        match draw() {
+            Some(card) => {
+                hand.push(card);
+                drawn.push(card);
+            }
+            None => break,
+        }
+    }
+    drawn
 }
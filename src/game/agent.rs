@@ -0,0 +1,203 @@
+//! Decision-makers that answer the dealer's requests
+use super::{Dealer, DealerRequest, Player, PlayerAction, PlayerActionError, SideBetKind};
+use crate::cards::Card;
+
+/// Makes decisions for a player and receives updates from the dealer
+///
+/// Each method matches a kind of `DealerRequest`.
+/// Methods for updates that do not need an answer have empty default implementations,
+/// so an agent only has to implement the ones it cares about.
+///
+/// Any `FnMut(DealerRequest, Option<&Player>, &Dealer) -> PlayerAction` is also an agent,
+/// which is passed the matching `DealerRequest` for every method.
+///
+/// # Examples
+///
+/// An agent that hits below 17 and keeps track of how many hands it played:
+///
+/// ```
+/// use twentyone::game::{get_hand_value, PlayerAgent};
+/// use twentyone::prelude::*;
+///
+/// struct Counter {
+///     hands: u32,
+/// }
+///
+/// impl PlayerAgent for Counter {
+///     fn bet(&mut self, _: &Player, _: &Dealer) -> PlayerAction {
+///         PlayerAction::Bet(10)
+///     }
+///
+///     fn play(&mut self, hand: usize, player: &Player, _: &Dealer) -> PlayerAction {
+///         self.hands += 1;
+///         if get_hand_value(&player.hands()[hand], true) < 17 {
+///             PlayerAction::Hit
+///         } else {
+///             PlayerAction::Stand
+///         }
+///     }
+/// }
+///
+/// let player = Player::with_agent(1000, Counter { hands: 0 });
+/// ```
+pub trait PlayerAgent {
+    /// Answers `DealerRequest::Bet` with `PlayerAction::Bet(i32)`,
+    /// or `PlayerAction::None` to sit out the round
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - The player being asked to bet
+    /// * `dealer` - The dealer asking
+    fn bet(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction;
+
+    /// Answers `DealerRequest::Play` with one of `PlayerAction::Hit`, `PlayerAction::Stand`,
+    /// `PlayerAction::DoubleDown`, `PlayerAction::Split` or `PlayerAction::Surrender`
+    ///
+    /// # Arguments
+    ///
+    /// * `hand` - The index of the hand to play
+    /// * `player` - The player being asked to play
+    /// * `dealer` - The dealer asking
+    fn play(&mut self, hand: usize, player: &Player, dealer: &Dealer) -> PlayerAction;
+
+    /// Answers `DealerRequest::Surrender` with `PlayerAction::Surrender`,
+    /// or `PlayerAction::None` to decline
+    ///
+    /// Only sent when early surrender is allowed, before the dealer peeks for blackjack.
+    /// Declines by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - The player being offered early surrender
+    /// * `dealer` - The dealer offering
+    fn surrender(&mut self, _player: &Player, _dealer: &Dealer) -> PlayerAction {
+        PlayerAction::None
+    }
+
+    /// Answers `DealerRequest::Insurance` with `PlayerAction::Insurance(i32)`,
+    /// or `PlayerAction::None` to decline
+    ///
+    /// If the player has a blackjack, any positive amount takes even money instead.
+    /// Declines by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - The player being offered insurance
+    /// * `dealer` - The dealer offering
+    fn insurance(&mut self, _player: &Player, _dealer: &Dealer) -> PlayerAction {
+        PlayerAction::None
+    }
+
+    /// Answers `DealerRequest::SideBet` with `PlayerAction::SideBet(SideBetKind, i32)`,
+    /// or `PlayerAction::None` to decline
+    ///
+    /// Sent once for each side bet offered at the table, after the player has bet.
+    /// Declines by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The side bet being offered
+    /// * `player` - The player being offered the side bet
+    /// * `dealer` - The dealer offering
+    fn side_bet(&mut self, _kind: SideBetKind, _player: &Player, _dealer: &Dealer) -> PlayerAction {
+        PlayerAction::None
+    }
+
+    /// Receives `DealerRequest::UpCard`
+    fn up_card(&mut self, _card: Card, _dealer: &Dealer) {}
+
+    /// Receives `DealerRequest::HitCard`
+    fn hit_card(&mut self, _card: Card, _dealer: &Dealer) {}
+
+    /// Receives `DealerRequest::DealerHand`
+    fn dealer_hand(&mut self, _hand: &[Card], _dealer: &Dealer) {}
+
+    /// Receives `DealerRequest::LowCards`
+    fn low_cards(&mut self, _dealer: &Dealer) {}
+
+    /// Receives `DealerRequest::Error` after this agent returned an invalid action
+    fn error(&mut self, _error: PlayerActionError, _player: &Player, _dealer: &Dealer) {}
+}
+
+impl<F> PlayerAgent for F
+where
+    F: FnMut(DealerRequest, Option<&Player>, &Dealer) -> PlayerAction,
+{
+    fn bet(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self(DealerRequest::Bet, Some(player), dealer)
+    }
+
+    fn play(&mut self, hand: usize, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self(DealerRequest::Play(hand), Some(player), dealer)
+    }
+
+    fn surrender(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self(DealerRequest::Surrender, Some(player), dealer)
+    }
+
+    fn insurance(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self(DealerRequest::Insurance, Some(player), dealer)
+    }
+
+    fn side_bet(&mut self, kind: SideBetKind, player: &Player, dealer: &Dealer) -> PlayerAction {
+        self(DealerRequest::SideBet(kind), Some(player), dealer)
+    }
+
+    fn up_card(&mut self, card: Card, dealer: &Dealer) {
+        self(DealerRequest::UpCard(card), None, dealer);
+    }
+
+    fn hit_card(&mut self, card: Card, dealer: &Dealer) {
+        self(DealerRequest::HitCard(card), None, dealer);
+    }
+
+    fn dealer_hand(&mut self, hand: &[Card], dealer: &Dealer) {
+        self(DealerRequest::DealerHand(hand.to_vec()), None, dealer);
+    }
+
+    fn low_cards(&mut self, dealer: &Dealer) {
+        self(DealerRequest::LowCards, None, dealer);
+    }
+
+    fn error(&mut self, error: PlayerActionError, player: &Player, dealer: &Dealer) {
+        self(DealerRequest::Error(error), Some(player), dealer);
+    }
+}
+
+/// Send a request to an agent through the matching method
+///
+/// `player` must be provided for requests that apply to a player.
+pub(super) fn send(
+    agent: &mut dyn PlayerAgent,
+    request: DealerRequest,
+    player: Option<&Player>,
+    dealer: &Dealer,
+) -> PlayerAction {
+    match request {
+        DealerRequest::Bet => agent.bet(player.unwrap(), dealer),
+        DealerRequest::Play(hand) => agent.play(hand, player.unwrap(), dealer),
+        DealerRequest::Surrender => agent.surrender(player.unwrap(), dealer),
+        DealerRequest::Insurance => agent.insurance(player.unwrap(), dealer),
+        DealerRequest::SideBet(kind) => agent.side_bet(kind, player.unwrap(), dealer),
+        DealerRequest::UpCard(card) => {
+            agent.up_card(card, dealer);
+            PlayerAction::None
+        }
+        DealerRequest::HitCard(card) => {
+            agent.hit_card(card, dealer);
+            PlayerAction::None
+        }
+        DealerRequest::DealerHand(hand) => {
+            agent.dealer_hand(&hand, dealer);
+            PlayerAction::None
+        }
+        DealerRequest::LowCards => {
+            agent.low_cards(dealer);
+            PlayerAction::None
+        }
+        DealerRequest::Error(error) => {
+            agent.error(error, player.unwrap(), dealer);
+            PlayerAction::None
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! A player's hand and the bet placed on it
+use super::HandValue;
+use crate::cards::Card;
+use std::ops::Deref;
+
+/// Describes one of a player's hands
+///
+/// Each hand has its own wager, so that hands from a split are settled separately.
+/// A hand dereferences to its cards, so it can be used anywhere a `&[Card]` is expected.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::game::{get_hand_value, Hand};
+///
+/// let mut hand = Hand::new(10);
+/// hand.cards_mut().push("AS".parse().unwrap());
+/// hand.cards_mut().push("KH".parse().unwrap());
+/// assert_eq!(get_hand_value(&hand, true), 21);
+/// assert_eq!(*hand.wager(), 10);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hand {
+    pub(super) cards: Vec<Card>,
+    pub(super) wager: i32,
+    pub(super) doubled: bool,
+    pub(super) split_from: Option<usize>,
+    pub(super) stood: bool,
+}
+
+impl Hand {
+    /// Returns a new empty Hand
+    ///
+    /// # Arguments
+    ///
+    /// * `wager` - The amount bet on the hand
+    pub fn new(wager: i32) -> Hand {
+        Hand {
+            wager,
+            ..Hand::default()
+        }
+    }
+
+    /// Returns a reference to the hand's cards
+    pub fn cards(&self) -> &Vec<Card> {
+        &self.cards
+    }
+
+    /// Returns a reference to the amount bet on the hand, including any double down
+    pub fn wager(&self) -> &i32 {
+        &self.wager
+    }
+
+    /// Returns the value of the hand's cards
+    pub fn value(&self) -> HandValue {
+        HandValue::new(&self.cards)
+    }
+
+    /// Returns whether the hand was doubled down
+    pub fn doubled(&self) -> bool {
+        self.doubled
+    }
+
+    /// Returns the index of the hand this hand was split from, if it was created by a split
+    pub fn split_from(&self) -> Option<usize> {
+        self.split_from
+    }
+
+    /// Returns whether the hand has finished being played
+    ///
+    /// Hands that stood, doubled down, busted or surrendered have finished.
+    pub fn stood(&self) -> bool {
+        self.stood
+    }
+
+    /// Returns a mutable reference to the hand's cards
+    pub fn cards_mut(&mut self) -> &mut Vec<Card> {
+        &mut self.cards
+    }
+
+    /// Returns a mutable reference to the amount bet on the hand
+    pub fn wager_mut(&mut self) -> &mut i32 {
+        &mut self.wager
+    }
+}
+
+impl Deref for Hand {
+    type Target = [Card];
+
+    fn deref(&self) -> &[Card] {
+        &self.cards
+    }
+}
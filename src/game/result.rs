@@ -0,0 +1,84 @@
+//! The outcome of a finished round
+use super::{SideBetKind, SideBetOutcome};
+use crate::cards::Card;
+
+/// How a hand was settled against the dealer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The hand beat the dealer, or the dealer busted
+    Win,
+    /// The hand lost to the dealer
+    Loss,
+    /// The hand tied with the dealer and the wager was returned
+    Push,
+    /// The hand was a blackjack and was paid at the blackjack payout
+    Blackjack,
+    /// The hand was surrendered
+    Surrender,
+    /// The hand went over 21
+    Bust,
+}
+
+/// The result of one of a player's hands
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandResult {
+    /// How the hand was settled
+    pub outcome: Outcome,
+    /// The amount bet on the hand, including any double down
+    pub wagered: i32,
+    /// The amount won (or lost, if negative) by the hand
+    pub net: i32,
+}
+
+/// The result of one of a player's side bets
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SideBetResult {
+    /// The kind of side bet
+    pub kind: SideBetKind,
+    /// How the side bet was settled
+    pub outcome: SideBetOutcome,
+    /// The amount bet on the side bet
+    pub wagered: i32,
+    /// The amount won (or lost, if negative) by the side bet
+    pub net: i32,
+}
+
+/// The result of a player's round
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlayerResult {
+    /// The results of each of the player's hands, in the same order as the hands
+    ///
+    /// Players who sat out the round have no hands.
+    pub hands: Vec<HandResult>,
+    /// The player's original bet, before any double downs or splits
+    pub bet: i32,
+    /// The amount won (or lost, if negative) from insurance
+    pub insurance: i32,
+    /// The results of each side bet the player placed, in the order they were offered
+    pub side_bets: Vec<SideBetResult>,
+}
+
+/// The result of a finished round, returned by [`Dealer::play_round`](super::Dealer::play_round)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundResult {
+    /// The results of each player, in the same order as the players
+    pub players: Vec<PlayerResult>,
+    /// The dealer's final hand
+    pub dealer_hand: Vec<Card>,
+    /// Whether the dealer busted
+    pub dealer_busted: bool,
+}
+
+impl PlayerResult {
+    /// Returns the total amount won (or lost, if negative) by the player in the round,
+    /// including insurance and side bets
+    pub fn net(&self) -> i32 {
+        self.hands.iter().map(|hand| hand.net).sum::<i32>()
+            + self.insurance
+            + self
+                .side_bets
+                .iter()
+                .map(|side_bet| side_bet.net)
+                .sum::<i32>()
+    }
+}
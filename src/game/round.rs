@@ -0,0 +1,851 @@
+//! Step-driven round state machine for the dealer
+use super::{
+    can_split, play_dealer_hand, Dealer, DealerRequest, Hand, HandResult, HandValue, HoleCardRule,
+    Outcome, PlayerAction, PlayerActionError, PlayerResult, RoundResult, SideBetKind,
+    SideBetOutcome, SideBetResult, SurrenderRule,
+};
+use crate::cards::{self, Card};
+use crate::shoe::ShuffleMode;
+
+/// A decision the dealer is waiting on before the round can continue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// Waiting for a player to bet
+    Bet {
+        /// The index of the player
+        player: usize,
+    },
+    /// Waiting for a player to place or decline a side bet
+    SideBet {
+        /// The index of the player
+        player: usize,
+        /// The side bet being offered
+        kind: SideBetKind,
+    },
+    /// Waiting for a player to take or decline early surrender
+    Surrender {
+        /// The index of the player
+        player: usize,
+    },
+    /// Waiting for a player to take or decline insurance
+    Insurance {
+        /// The index of the player
+        player: usize,
+    },
+    /// Waiting for a player to play a hand
+    Play {
+        /// The index of the player
+        player: usize,
+        /// The index of the hand to play
+        hand: usize,
+    },
+}
+
+impl Decision {
+    /// Returns the index of the player that must make the decision
+    pub fn player(&self) -> usize {
+        match *self {
+            Decision::Bet { player } => player,
+            Decision::SideBet { player, .. } => player,
+            Decision::Surrender { player } => player,
+            Decision::Insurance { player } => player,
+            Decision::Play { player, .. } => player,
+        }
+    }
+
+    /// Returns the `DealerRequest` that would be sent to a callback for the decision
+    pub fn request(&self) -> DealerRequest {
+        match *self {
+            Decision::Bet { .. } => DealerRequest::Bet,
+            Decision::SideBet { kind, .. } => DealerRequest::SideBet(kind),
+            Decision::Surrender { .. } => DealerRequest::Surrender,
+            Decision::Insurance { .. } => DealerRequest::Insurance,
+            Decision::Play { hand, .. } => DealerRequest::Play(hand),
+        }
+    }
+}
+
+/// The current step of a round
+#[derive(Clone, Copy)]
+enum Phase {
+    /// No round is in progress
+    Idle,
+    /// Collecting the bet of a player
+    Betting(usize),
+    /// Offering one of the table's side bets to a player
+    SideBet { player: usize, index: usize },
+    /// Offering early surrender to a player
+    Surrender(usize),
+    /// Offering insurance to a player
+    Insurance(usize),
+    /// Collecting actions for a player's hand
+    Playing { player: usize, hand: usize },
+}
+
+/// A side bet placed by a player
+struct PlacedSideBet {
+    kind: SideBetKind,
+    wager: i32,
+    /// The outcome, once the side bet has been settled
+    outcome: Option<SideBetOutcome>,
+}
+
+/// State kept by the dealer while a round is in progress
+pub(super) struct Round {
+    phase: Phase,
+    /// The original bet of each player, before doubling down or splitting
+    bets: Vec<i32>,
+    /// Whether each player surrendered
+    surrendered: Vec<bool>,
+    /// The amount of insurance taken by each player
+    insurance: Vec<i32>,
+    /// Whether each player took even money
+    even_money: Vec<bool>,
+    /// Whether each player split aces
+    split_aces: Vec<bool>,
+    /// The side bets placed by each player
+    side_bets: Vec<Vec<PlacedSideBet>>,
+    /// The first two cards dealt to each player, used to settle side bets
+    dealt: Vec<Vec<Card>>,
+    /// Whether the current player can surrender
+    can_surrender: bool,
+    /// The result of the round, once it is over
+    result: Option<RoundResult>,
+}
+
+impl Round {
+    pub(super) fn new() -> Round {
+        Round {
+            phase: Phase::Idle,
+            bets: Vec::new(),
+            surrendered: Vec::new(),
+            insurance: Vec::new(),
+            even_money: Vec::new(),
+            split_aces: Vec::new(),
+            side_bets: Vec::new(),
+            dealt: Vec::new(),
+            can_surrender: false,
+            result: None,
+        }
+    }
+}
+
+impl Dealer<'_> {
+    /// Start a new round of blackjack
+    ///
+    /// After starting a round, use [`pending`](Dealer::pending) to get the decision
+    /// the dealer is waiting on, and [`apply`](Dealer::apply) to make it.
+    /// Any round already in progress is abandoned.
+    ///
+    /// If the cut card has come out, the shoe is reshuffled first,
+    /// unless nothing has been dealt from it yet.
+    /// Continuous shoes have the discard tray put back in instead.
+    /// Cards chosen with [`rig`](Dealer::rig) are put on top of the shoe once every player has bet.
+    ///
+    /// Returns the events that happened while starting the round.
+    ///
+    /// # Arguments
+    ///
+    /// * `clear_table` - Clear the table at the beginning of the round
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::game::{get_hand_value, Decision};
+    /// use twentyone::prelude::*;
+    /// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+    /// #     PlayerAction::None
+    /// # }
+    ///
+    /// let mut shoe = create_shoe(6);
+    /// shuffle_deck(&mut shoe);
+    /// let mut dealer = Dealer::new(shoe, DEFAULT_CONFIG, &callback);
+    /// dealer.players_mut().push(Player::new(1000));
+    ///
+    /// dealer.start_round(true);
+    /// while let Some(decision) = dealer.pending() {
+    ///     let action = match decision {
+    ///         Decision::Bet { .. } => PlayerAction::Bet(10),
+    ///         Decision::SideBet { .. }
+    ///         | Decision::Surrender { .. }
+    ///         | Decision::Insurance { .. } => PlayerAction::None,
+    ///         Decision::Play { player, hand } => {
+    ///             if get_hand_value(&dealer.players()[player].hands()[hand], true) < 17 {
+    ///                 PlayerAction::Hit
+    ///             } else {
+    ///                 PlayerAction::Stand
+    ///             }
+    ///         }
+    ///     };
+    ///     for event in dealer.apply(action).unwrap() {
+    ///         if let DealerRequest::UpCard(card) = event {
+    ///             println!("Dealer up card: {}", card);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn start_round(&mut self, clear_table: bool) -> Vec<DealerRequest> {
+        if clear_table {
+            self.clear_table();
+        }
+
+        self.round = Round::new();
+        self.round.phase = Phase::Betting(0);
+
+        let mut events = Vec::new();
+        if let ShuffleMode::Continuous { .. } = self.shoe.mode() {
+            // Continuous shuffling machines take the discards back every round
+            self.shoe.reinsert_discards(&mut self.rng);
+        } else if self.shoe.dealt() > 0
+            && (self.shoe.cut_card_reached()
+                || self.shoe.remaining() <= self.config.low_cards_threshold)
+        {
+            // Reshuffle between rounds once the cut card has come out or the shoe is low.
+            // Shoes that haven't been dealt from yet (eg. stacked shoes) are dealt as they are.
+            self.shuffle_shoe();
+            events.push(DealerRequest::LowCards);
+        }
+        self.advance(&mut events);
+        self.announce_reshuffle(&mut events);
+        events
+    }
+
+    /// Returns the result of the round, once it is over
+    ///
+    /// Returns `None` while a round is in progress, or if no round has been played.
+    pub fn result(&self) -> Option<&RoundResult> {
+        self.round.result.as_ref()
+    }
+
+    /// Returns the decision the dealer is currently waiting on
+    ///
+    /// Returns `None` if no round is in progress.
+    pub fn pending(&self) -> Option<Decision> {
+        match self.round.phase {
+            Phase::Idle => None,
+            Phase::Betting(player) => Some(Decision::Bet { player }),
+            Phase::SideBet { player, index } => Some(Decision::SideBet {
+                player,
+                kind: self.side_bets[index].kind(),
+            }),
+            Phase::Surrender(player) => Some(Decision::Surrender { player }),
+            Phase::Insurance(player) => Some(Decision::Insurance { player }),
+            Phase::Playing { player, hand } => Some(Decision::Play { player, hand }),
+        }
+    }
+
+    /// Returns whether the hand waiting to be played can be doubled down
+    ///
+    /// Checks both the game's rules and whether the player can afford it.
+    /// Returns `false` if no hand is waiting to be played.
+    pub fn can_double(&self) -> bool {
+        match self.round.phase {
+            Phase::Playing { player, hand } if self.is_playing(player, hand) => {
+                self.can_double_hand(player, hand)
+                    && self.players[player].money() >= self.players[player].hands()[hand].wager()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether the hand waiting to be played can be split
+    ///
+    /// Checks both the game's rules and whether the player can afford it.
+    /// Returns `false` if no hand is waiting to be played.
+    pub fn can_split(&self) -> bool {
+        match self.round.phase {
+            Phase::Playing { player, hand } if self.is_playing(player, hand) => {
+                self.can_split_hand(player, hand)
+                    && self.players[player].money() >= &self.round.bets[player]
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether the pending decision allows surrendering
+    ///
+    /// This is always `true` when early surrender is being offered.
+    pub fn can_surrender(&self) -> bool {
+        match self.round.phase {
+            Phase::Surrender(_) => true,
+            Phase::Playing { .. } => self.round.can_surrender,
+            _ => false,
+        }
+    }
+
+    /// Make the pending decision and move the round forward
+    ///
+    /// Returns the events that happened before the next decision was reached
+    /// (eg. the dealer's up card after bets are placed, or the dealer's hits and hand
+    /// at the end of the round).
+    /// If the action is not valid for the pending decision, the round does not move
+    /// and the same decision remains pending.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action to perform
+    ///
+    /// # Callback equivalents
+    ///
+    /// | `Decision`            | `PlayerAction`                                                                                                                  |
+    /// |-----------------------|---------------------------------------------------------------------------------------------------------------------------------|
+    /// | `Decision::Bet`       | `PlayerAction::Bet(i32)`, or `PlayerAction::None` to sit out the round                                                          |
+    /// | `Decision::SideBet`   | `PlayerAction::SideBet(SideBetKind, i32)` with the offered kind, or `PlayerAction::None`                                        |
+    /// | `Decision::Surrender` | `PlayerAction::Surrender` or `PlayerAction::None`                                                                               |
+    /// | `Decision::Insurance` | `PlayerAction::Insurance(i32)` or `PlayerAction::None`                                                                          |
+    /// | `Decision::Play`      | One of `PlayerAction::Hit`, `PlayerAction::Stand`, `PlayerAction::DoubleDown`, `PlayerAction::Split`, `PlayerAction::Surrender` |
+    pub fn apply(&mut self, action: PlayerAction) -> Result<Vec<DealerRequest>, PlayerActionError> {
+        match self.round.phase {
+            Phase::Idle => return Err(PlayerActionError::UnexpectedAction(0, action)),
+            Phase::Betting(player) => self.apply_bet(player, action)?,
+            Phase::SideBet { player, index } => self.apply_side_bet(player, index, action)?,
+            Phase::Surrender(player) => self.apply_surrender(player, action)?,
+            Phase::Insurance(player) => self.apply_insurance(player, action)?,
+            Phase::Playing { player, hand } => self.apply_play(player, hand, action)?,
+        }
+
+        let mut events = Vec::new();
+        self.advance(&mut events);
+        self.announce_reshuffle(&mut events);
+        Ok(events)
+    }
+
+    /// Place a player's bet
+    fn apply_bet(&mut self, player: usize, action: PlayerAction) -> Result<(), PlayerActionError> {
+        let amount = match action {
+            PlayerAction::Bet(amount) => amount,
+            PlayerAction::None => {
+                // Players sitting out have no hands for the round
+                self.round.bets.push(0);
+                self.round.surrendered.push(false);
+                self.round.insurance.push(0);
+                self.round.even_money.push(false);
+                self.round.split_aces.push(false);
+                self.round.side_bets.push(Vec::new());
+                self.players[player].hands.clear();
+                self.round.phase = Phase::Betting(player + 1);
+                return Ok(());
+            }
+            _ => return Err(PlayerActionError::UnexpectedAction(0, action)),
+        };
+
+        // Check if player can afford bet and if it is within limits
+        if self.players[player].money() < &amount {
+            return Err(PlayerActionError::NotEnoughMoney(0, action));
+        }
+        if amount < self.config.min_bet || self.config.max_bet < amount {
+            return Err(PlayerActionError::UnexpectedAction(0, action));
+        }
+
+        self.round.bets.push(amount);
+        self.round.surrendered.push(false);
+        self.round.insurance.push(0);
+        self.round.even_money.push(false);
+        self.round.split_aces.push(false);
+        self.round.side_bets.push(Vec::new());
+        // Players who sat out the last round have no hand if the table wasn't cleared
+        if self.sitting_out(player) {
+            self.players[player].hands.push(Hand::default());
+        }
+        self.players[player].hands[0].wager = amount;
+        *self.players[player].money_mut() -= amount;
+        // Offer the table's side bets before moving on to the next player
+        self.round.phase = Phase::SideBet { player, index: 0 };
+        Ok(())
+    }
+
+    /// Place or decline a player's side bet
+    fn apply_side_bet(
+        &mut self,
+        player: usize,
+        index: usize,
+        action: PlayerAction,
+    ) -> Result<(), PlayerActionError> {
+        match action {
+            PlayerAction::SideBet(kind, wager) if kind == self.side_bets[index].kind() => {
+                if wager <= 0 {
+                    return Err(PlayerActionError::UnexpectedAction(0, action));
+                }
+                if self.players[player].money() < &wager {
+                    return Err(PlayerActionError::NotEnoughMoney(0, action));
+                }
+                *self.players[player].money_mut() -= wager;
+                self.round.side_bets[player].push(PlacedSideBet {
+                    kind,
+                    wager,
+                    outcome: None,
+                });
+            }
+            PlayerAction::None => (),
+            _ => return Err(PlayerActionError::UnexpectedAction(0, action)),
+        }
+
+        self.round.phase = Phase::SideBet {
+            player,
+            index: index + 1,
+        };
+        Ok(())
+    }
+
+    /// Take or decline a player's early surrender
+    fn apply_surrender(
+        &mut self,
+        player: usize,
+        action: PlayerAction,
+    ) -> Result<(), PlayerActionError> {
+        match action {
+            PlayerAction::Surrender => self.round.surrendered[player] = true,
+            PlayerAction::None => (),
+            _ => return Err(PlayerActionError::UnexpectedAction(0, action)),
+        }
+
+        self.round.phase = Phase::Surrender(player + 1);
+        Ok(())
+    }
+
+    /// Take or decline a player's insurance
+    fn apply_insurance(
+        &mut self,
+        player: usize,
+        action: PlayerAction,
+    ) -> Result<(), PlayerActionError> {
+        let amount = match action {
+            PlayerAction::Insurance(amount) => amount,
+            PlayerAction::None => 0,
+            _ => return Err(PlayerActionError::UnexpectedAction(0, action)),
+        };
+
+        let bet = self.round.bets[player];
+        if amount > 0 && self.players[player].hands()[0].value().is_blackjack {
+            // Players with blackjack take even money, which is paid immediately
+            // and doesn't depend on the amount of insurance
+            self.round.even_money[player] = true;
+            *self.players[player].money_mut() += bet * 2;
+        } else {
+            // Insurance is limited to half of the bet, rounded up so that odd bets can be insured
+            if amount < 0 || amount > (bet + 1) / 2 {
+                return Err(PlayerActionError::UnexpectedAction(0, action));
+            }
+            if self.players[player].money() < &amount {
+                return Err(PlayerActionError::NotEnoughMoney(0, action));
+            }
+            self.round.insurance[player] = amount;
+            *self.players[player].money_mut() -= amount;
+        }
+
+        self.round.phase = Phase::Insurance(player + 1);
+        Ok(())
+    }
+
+    /// Perform an action on a player's hand
+    fn apply_play(
+        &mut self,
+        player: usize,
+        hand: usize,
+        action: PlayerAction,
+    ) -> Result<(), PlayerActionError> {
+        // Split aces can't take more cards unless allowed
+        let one_card_only = self.round.split_aces[player] && !self.config.hit_split_aces;
+        match action {
+            PlayerAction::Hit => {
+                if one_card_only {
+                    return Err(PlayerActionError::UnexpectedAction(hand, action));
+                }
+                self.hit_card(player, hand);
+                self.round.can_surrender = false;
+            }
+            PlayerAction::Stand => self.players[player].hands[hand].stood = true,
+            PlayerAction::DoubleDown => {
+                if !self.can_double_hand(player, hand) {
+                    return Err(PlayerActionError::UnexpectedAction(hand, action));
+                }
+                // Doubling matches the hand's wager
+                let wager = self.players[player].hands[hand].wager;
+                if self.players[player].money() < &wager {
+                    return Err(PlayerActionError::NotEnoughMoney(hand, action));
+                }
+                *self.players[player].money_mut() -= wager;
+                let doubled = &mut self.players[player].hands[hand];
+                doubled.wager += wager;
+                doubled.doubled = true;
+                doubled.stood = true;
+                self.hit_card(player, hand);
+                self.round.can_surrender = false;
+            }
+            PlayerAction::Split => {
+                if !self.can_split_hand(player, hand) {
+                    return Err(PlayerActionError::UnexpectedAction(hand, action));
+                }
+                let bet = self.round.bets[player];
+                if self.players[player].money() < &bet {
+                    return Err(PlayerActionError::NotEnoughMoney(hand, action));
+                }
+                *self.players[player].money_mut() -= bet;
+                let aces = self.players[player].hands()[hand][0].rank == cards::Rank::Ace;
+
+                // Move the second card into a new hand, played right after this one
+                let card = self.players[player].hands[hand].cards.pop().unwrap();
+                let mut split = Hand::new(bet);
+                split.cards.push(card);
+                split.split_from = Some(hand);
+                self.players[player].hands.insert(hand + 1, split);
+                // Hit another card to each hand
+                self.hit_card(player, hand);
+                self.hit_card(player, hand + 1);
+
+                if aces {
+                    self.round.split_aces[player] = true;
+                    // Split aces receive one card each, but may still be resplit
+                    if !self.config.hit_split_aces {
+                        for &i in [hand, hand + 1].iter() {
+                            self.players[player].hands[i].stood = !self.can_split_hand(player, i);
+                        }
+                    }
+                }
+                self.round.can_surrender = false;
+            }
+            PlayerAction::Surrender => {
+                if !self.round.can_surrender {
+                    return Err(PlayerActionError::SurrenderNotAllowed(hand));
+                }
+                self.round.surrendered[player] = true;
+                self.players[player].hands[hand].stood = true;
+            }
+            _ => return Err(PlayerActionError::UnexpectedAction(hand, action)),
+        }
+
+        // Check if the hand is busted
+        if self.players[player].hands()[hand].value().is_bust {
+            self.players[player].hands[hand].stood = true;
+        }
+        Ok(())
+    }
+
+    /// Run the round until a decision is needed or the round is over
+    fn advance(&mut self, events: &mut Vec<DealerRequest>) {
+        loop {
+            match self.round.phase {
+                Phase::Idle => return,
+                Phase::Betting(player) => {
+                    if player < self.players.len() {
+                        return;
+                    }
+                    // All bets are in, deal hands
+                    self.stack_rigged();
+                    self.deal_hands();
+                    self.settle_side_bets_on_deal();
+                    // Send dealer up card
+                    events.push(DealerRequest::UpCard(self.hand[0]));
+                    self.round.phase = Phase::Surrender(0);
+                }
+                Phase::SideBet { player, index } => {
+                    if index < self.side_bets.len() {
+                        return;
+                    }
+                    self.round.phase = Phase::Betting(player + 1);
+                }
+                Phase::Surrender(player) => {
+                    // Offer early surrender before the dealer peeks
+                    let offered = self.config.surrender == SurrenderRule::Early
+                        && self.config.hole_card == HoleCardRule::Peek
+                        && self.dealer_may_have_blackjack();
+                    if offered && player < self.players.len() {
+                        if !self.sitting_out(player) {
+                            return;
+                        }
+                        self.round.phase = Phase::Surrender(player + 1);
+                        continue;
+                    }
+                    self.round.phase = Phase::Insurance(0);
+                }
+                Phase::Insurance(player) => {
+                    // Offer insurance if the dealer might have blackjack
+                    let offered = self.config.insurance && self.hand[0].rank == cards::Rank::Ace;
+                    if offered && player < self.players.len() {
+                        if !self.round.surrendered[player] && !self.sitting_out(player) {
+                            return;
+                        }
+                        self.round.phase = Phase::Insurance(player + 1);
+                        continue;
+                    }
+
+                    // Peek for blackjack, skipping straight to the dealer if they have one
+                    if self.config.hole_card == HoleCardRule::Peek
+                        && self.dealer_may_have_blackjack()
+                        && HandValue::new(&self.hand).is_blackjack
+                    {
+                        self.start_turn(self.players.len());
+                    } else {
+                        self.start_turn(0);
+                    }
+                }
+                Phase::Playing { player, hand } => {
+                    if player >= self.players.len() {
+                        let busted = self.play_dealer(events);
+                        self.pay_out(busted);
+                        events.push(DealerRequest::DealerHand(self.hand.clone()));
+                        self.round.phase = Phase::Idle;
+                        return;
+                    }
+
+                    let hands = self.players[player].hands();
+                    if hand >= hands.len() {
+                        self.start_turn(player + 1);
+                    } else if hands[hand].stood {
+                        self.round.phase = Phase::Playing {
+                            player,
+                            hand: hand + 1,
+                        };
+                    } else {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Prepare a player's turn
+    fn start_turn(&mut self, player: usize) {
+        self.round.phase = Phase::Playing { player, hand: 0 };
+        if player >= self.players.len() || self.sitting_out(player) {
+            return;
+        }
+
+        // Surrendering is only allowed as the first action
+        self.round.can_surrender = self.config.surrender != SurrenderRule::None;
+        // Hands that took even money have already been paid
+        self.players[player].hands[0].stood =
+            self.round.even_money[player] || self.round.surrendered[player];
+    }
+
+    /// Put the cards chosen with `rig` on top of the shoe, in the order they are dealt
+    fn stack_rigged(&mut self) {
+        let rigged = match self.rigged.take() {
+            Some(rigged) => rigged,
+            None => return,
+        };
+        let peek = self.config.hole_card == HoleCardRule::Peek;
+        // Match the order of `deal_hands`, which skips players sitting out
+        let seated: Vec<[Card; 2]> = rigged
+            .players
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i < self.players.len() && !self.sitting_out(i))
+            .map(|(_, &cards)| cards)
+            .collect();
+        let mut cards = Vec::new();
+        for i in 0..2 {
+            cards.extend(seated.iter().map(|pair| pair[i]));
+            if i == 0 || peek {
+                cards.push(rigged.dealer[i]);
+            }
+        }
+        cards.extend(rigged.hits);
+        if !peek {
+            cards.push(rigged.dealer[1]);
+        }
+        self.shoe.stack(cards);
+    }
+
+    /// Send `LowCards` first if the shoe ran out and was reshuffled while drawing
+    fn announce_reshuffle(&mut self, events: &mut Vec<DealerRequest>) {
+        if std::mem::take(&mut self.reshuffled) && !events.contains(&DealerRequest::LowCards) {
+            events.insert(0, DealerRequest::LowCards);
+        }
+    }
+
+    /// Returns whether a player is sitting out the round
+    fn sitting_out(&self, player: usize) -> bool {
+        self.players[player].hands.is_empty()
+    }
+
+    /// Returns whether a player's hand exists and is being played
+    fn is_playing(&self, player: usize, hand: usize) -> bool {
+        player < self.players.len() && hand < self.players[player].hands().len()
+    }
+
+    /// Returns whether a player's hand can be doubled down under the game's rules
+    ///
+    /// Does not check whether the player can afford to double down.
+    fn can_double_hand(&self, player: usize, hand: usize) -> bool {
+        let hands = self.players[player].hands();
+        self.config.doubling_down
+            && hands[hand].len() == 2
+            && !hands[hand].stood
+            && (hands.len() == 1 || self.config.double_after_split)
+            // Split aces can only be doubled if they can be hit
+            && (!self.round.split_aces[player] || self.config.hit_split_aces)
+    }
+
+    /// Returns whether a player's hand can be split under the game's rules
+    ///
+    /// Does not check whether the player can afford to split.
+    fn can_split_hand(&self, player: usize, hand: usize) -> bool {
+        let hands = self.players[player].hands();
+        let cards = &hands[hand];
+        self.config.splitting
+            && hands.len() < self.config.max_split_hands
+            && cards.len() == 2
+            && can_split(cards)
+            // Check if aces can be resplit
+            && !(cards[0].rank == cards::Rank::Ace
+                && self.round.split_aces[player]
+                && !self.config.resplit_aces)
+    }
+
+    /// Returns whether the dealer's up card is an ace or a ten-value card
+    fn dealer_may_have_blackjack(&self) -> bool {
+        self.hand[0].rank == cards::Rank::Ace || self.hand[0].value() == 10
+    }
+
+    /// Play out the dealer's hand, returning whether the dealer busted
+    fn play_dealer(&mut self, events: &mut Vec<DealerRequest>) -> bool {
+        // The hand is taken out so that the dealer can draw while it is played.
+        // In no-hole-card games, this also deals the dealer's second card.
+        let mut hand = std::mem::take(&mut self.hand);
+        let config = self.config;
+        let drawn = play_dealer_hand(&mut hand, &config, || Some(self.draw_card()));
+        self.hand = hand;
+        events.extend(drawn.into_iter().map(DealerRequest::HitCard));
+        HandValue::new(&self.hand).is_bust
+    }
+
+    /// Settle the side bets that are decided once the hands have been dealt
+    fn settle_side_bets_on_deal(&mut self) {
+        let up_card = self.hand[0];
+        for (i, player) in self.players.iter_mut().enumerate() {
+            let dealt = match player.hands.first() {
+                Some(hand) => hand.cards.clone(),
+                None => Vec::new(),
+            };
+            for placed in self.round.side_bets[i].iter_mut() {
+                let side_bet = self
+                    .side_bets
+                    .iter()
+                    .find(|side_bet| side_bet.kind() == placed.kind);
+                placed.outcome =
+                    side_bet.and_then(|side_bet| side_bet.settle_on_deal(&dealt, up_card));
+                if let Some(outcome) = placed.outcome {
+                    player.money += placed.wager + outcome.net(placed.wager);
+                }
+            }
+            self.round.dealt.push(dealt);
+        }
+    }
+
+    /// Pay out winners and record the result of the round
+    ///
+    /// Each hand is settled against the dealer with its own wager.
+    fn pay_out(&mut self, busted: bool) {
+        let dealer_value = HandValue::new(&self.hand);
+        let dealer_blackjack = dealer_value.is_blackjack;
+        let original_bets_only = self.config.hole_card
+            == (HoleCardRule::NoHoleCard {
+                original_bets_only: true,
+            });
+        let mut results = Vec::with_capacity(self.players.len());
+        for (i, player) in self.players.iter_mut().enumerate() {
+            let bet = self.round.bets[i];
+            // Pay out insurance 2 to 1
+            let insurance = self.round.insurance[i];
+            let mut result = PlayerResult {
+                hands: Vec::with_capacity(player.hands.len()),
+                bet,
+                insurance: if dealer_blackjack {
+                    insurance * 2
+                } else {
+                    -insurance
+                },
+                side_bets: Vec::with_capacity(self.round.side_bets[i].len()),
+            };
+            player.money += insurance + result.insurance;
+
+            // Split hands only count as blackjack if they came from aces and it is allowed
+            let blackjack_allowed = player.hands.len() == 1
+                || (self.round.split_aces[i] && self.config.split_ace_blackjack);
+            for (j, hand) in player.hands.iter().enumerate() {
+                let wager = hand.wager;
+                let value = hand.value();
+                let blackjack = value.is_blackjack && blackjack_allowed;
+
+                let (outcome, net) = if self.round.even_money[i] {
+                    // Even money is a win paid 1 to 1
+                    (Outcome::Win, wager)
+                } else if self.round.surrendered[i] {
+                    // Refund half of surrendered bets
+                    if self.config.surrender == SurrenderRule::Early || !dealer_blackjack {
+                        (Outcome::Surrender, wager / 2 - wager)
+                    } else {
+                        (Outcome::Surrender, -wager)
+                    }
+                } else if value.is_bust {
+                    (Outcome::Bust, -wager)
+                } else if blackjack {
+                    if dealer_blackjack {
+                        (Outcome::Push, 0)
+                    } else {
+                        // Pay out 3 to 2
+                        let winnings = (wager as f32 * self.config.blackjack_payout) as i32;
+                        (Outcome::Blackjack, winnings)
+                    }
+                } else if dealer_blackjack {
+                    // Return extra bets from doubling down and splitting
+                    // if only original bets are lost to a no-hole-card blackjack
+                    if original_bets_only {
+                        (Outcome::Loss, if j == 0 { -bet } else { 0 })
+                    } else {
+                        (Outcome::Loss, -wager)
+                    }
+                } else if busted || value.total > dealer_value.total {
+                    (Outcome::Win, wager)
+                } else if value.total == dealer_value.total {
+                    (Outcome::Push, 0)
+                } else {
+                    (Outcome::Loss, -wager)
+                };
+
+                // Even money was paid when it was taken
+                if !self.round.even_money[i] {
+                    player.money += wager + net;
+                }
+                result.hands.push(HandResult {
+                    outcome,
+                    wagered: wager,
+                    net,
+                });
+            }
+
+            // Settle the remaining side bets with the dealer's final hand
+            let (dealt, dealer_hand) = (&self.round.dealt[i], &self.hand);
+            for placed in self.round.side_bets[i].iter() {
+                let outcome = match placed.outcome {
+                    Some(outcome) => outcome,
+                    None => {
+                        let outcome = self
+                            .side_bets
+                            .iter()
+                            .find(|side_bet| side_bet.kind() == placed.kind)
+                            .map_or(SideBetOutcome::Loss, |side_bet| {
+                                side_bet.settle_on_dealer_final(dealt, dealer_hand)
+                            });
+                        player.money += placed.wager + outcome.net(placed.wager);
+                        outcome
+                    }
+                };
+                result.side_bets.push(SideBetResult {
+                    kind: placed.kind,
+                    outcome,
+                    wagered: placed.wager,
+                    net: outcome.net(placed.wager),
+                });
+            }
+            results.push(result);
+        }
+
+        self.round.result = Some(RoundResult {
+            players: results,
+            dealer_hand: self.hand.clone(),
+            dealer_busted: busted,
+        });
+    }
+}
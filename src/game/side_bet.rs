@@ -0,0 +1,104 @@
+//! Optional wagers placed alongside the main bet
+use crate::cards::Card;
+
+/// Identifies a kind of side bet
+///
+/// Used to offer side bets to players and to place them with `PlayerAction::SideBet`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SideBetKind {
+    /// Pays when the player's first two cards are a pair
+    PerfectPairs,
+    /// Pays when the player's first two cards and the dealer's up card make a poker hand
+    TwentyOnePlusThree,
+    /// Pays when the player's first two cards total 20
+    LuckyLadies,
+    /// Pays when the dealer busts, based on the amount of cards in their hand
+    Buster,
+    /// Any other side bet, identified by its name
+    Other(&'static str),
+}
+
+/// How a side bet was settled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SideBetOutcome {
+    /// The side bet won and was paid at the given odds to 1
+    Win(u32),
+    /// The side bet lost
+    Loss,
+}
+
+impl SideBetOutcome {
+    /// Returns the amount won (or lost, if negative) by a side bet with this outcome
+    ///
+    /// # Arguments
+    ///
+    /// * `wager` - The amount bet on the side bet
+    pub fn net(self, wager: i32) -> i32 {
+        match self {
+            SideBetOutcome::Win(odds) => wager.saturating_mul(odds as i32),
+            SideBetOutcome::Loss => -wager,
+        }
+    }
+}
+
+/// A side bet offered by the dealer
+///
+/// Side bets are offered to each player after they place their main bet,
+/// and are settled separately from the player's hands.
+/// A side bet is settled as soon as [`settle_on_deal`](SideBet::settle_on_deal)
+/// returns an outcome, or with the dealer's final hand otherwise.
+/// Both are given the player's first two cards, even if the hand was later split.
+///
+/// # Examples
+///
+/// A side bet that pays 3 to 1 when the dealer's up card is an ace:
+///
+/// ```
+/// use twentyone::cards::{Card, Rank};
+/// use twentyone::game::{SideBet, SideBetKind, SideBetOutcome};
+///
+/// struct AceUp;
+///
+/// impl SideBet for AceUp {
+///     fn kind(&self) -> SideBetKind {
+///         SideBetKind::Other("Ace Up")
+///     }
+///
+///     fn settle_on_deal(&self, _: &[Card], up_card: Card) -> Option<SideBetOutcome> {
+///         if up_card.rank == Rank::Ace {
+///             Some(SideBetOutcome::Win(3))
+///         } else {
+///             Some(SideBetOutcome::Loss)
+///         }
+///     }
+/// }
+/// ```
+pub trait SideBet {
+    /// Returns the kind of side bet
+    fn kind(&self) -> SideBetKind;
+
+    /// Settle the side bet once the hands have been dealt,
+    /// or return `None` to wait for the dealer's final hand
+    ///
+    /// Waits by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `hand` - The player's first two cards
+    /// * `up_card` - The dealer's up card
+    fn settle_on_deal(&self, _hand: &[Card], _up_card: Card) -> Option<SideBetOutcome> {
+        None
+    }
+
+    /// Settle the side bet once the dealer has finished playing
+    ///
+    /// Only called if the side bet wasn't settled on the deal. Loses by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `hand` - The player's first two cards
+    /// * `dealer_hand` - The dealer's final hand
+    fn settle_on_dealer_final(&self, _hand: &[Card], _dealer_hand: &[Card]) -> SideBetOutcome {
+        SideBetOutcome::Loss
+    }
+}
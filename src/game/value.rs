@@ -0,0 +1,84 @@
+//! Soft and hard hand evaluation
+use crate::cards::{Card, Rank};
+use std::fmt;
+
+/// The value of a hand, along with whether it is soft, a blackjack, busted or a pair
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::parse_cards;
+/// use twentyone::game::HandValue;
+///
+/// let value = HandValue::new(&parse_cards("AS 7H").unwrap());
+/// assert_eq!(value.total, 18);
+/// assert!(value.soft);
+/// assert_eq!(value.to_string(), "soft 18");
+///
+/// let value = HandValue::new(&parse_cards("AS 7H KD").unwrap());
+/// assert_eq!(value.total, 18);
+/// assert!(!value.soft);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HandValue {
+    /// The best total of the hand, counting an ace as 11 if it doesn't bust the hand
+    pub total: u8,
+    /// Whether an ace is counted as 11
+    pub soft: bool,
+    /// Whether the hand is a natural (an ace and a ten-value card as the only two cards)
+    ///
+    /// Whether a natural from a split is paid as a blackjack depends on the game's rules.
+    pub is_blackjack: bool,
+    /// Whether the total is over 21
+    pub is_bust: bool,
+    /// Whether the hand is two cards of the same rank
+    pub is_pair: bool,
+}
+
+impl HandValue {
+    /// Returns the value of a hand
+    ///
+    /// # Arguments
+    ///
+    /// * `hand` - The hand to get the value of
+    pub fn new(hand: &[Card]) -> HandValue {
+        // Count aces as 1, then count one as 11 if it doesn't bust the hand
+        let hard: u8 = hand
+            .iter()
+            .map(|card| match card.rank {
+                Rank::Ace => 1,
+                _ => card.value(),
+            })
+            .sum();
+        let soft = hand.iter().any(|card| card.rank == Rank::Ace) && hard + 10 <= 21;
+        let total = if soft { hard + 10 } else { hard };
+
+        HandValue {
+            total,
+            soft,
+            is_blackjack: total == 21 && hand.len() == 2,
+            is_bust: total > 21,
+            is_pair: hand.len() == 2 && hand[0].rank == hand[1].rank,
+        }
+    }
+
+    /// Returns the total of the hand with every ace counted as 1
+    pub fn hard_total(&self) -> u8 {
+        if self.soft {
+            self.total - 10
+        } else {
+            self.total
+        }
+    }
+}
+
+/// Formats a hand value as its total, with whether it is soft or hard (eg. `soft 18`)
+impl fmt::Display for HandValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.soft {
+            write!(f, "soft {}", self.total)
+        } else {
+            write!(f, "hard {}", self.total)
+        }
+    }
+}
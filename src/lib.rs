@@ -11,7 +11,7 @@
 //! use twentyone::prelude::*;
 //!
 //! // Callback that will be used to return player's actions such as betting or hitting
-//! fn callback(request: DealerRequest, player: Option<&Player>, dealer: &Dealer) -> PlayerAction {
+//! fn callback(request: DealerRequest, player: Option<&Player>, _: &Dealer) -> PlayerAction {
 //!     match request {
 //!         // Dealer asking player to play, along with a hand index
 //!         DealerRequest::Play(i) => {
@@ -33,26 +33,6 @@
 //!             println!("Player is betting $10");
 //!             PlayerAction::Bet(10)
 //!         }
-//!         // Dealer showing their hand when the game is over
-//!         DealerRequest::DealerHand(hand) => {
-//!             // Get the value of the player's and the dealer's hand
-//!             let dealer_hand_value = get_hand_value(&hand, true);
-//!             let player_hand_value = get_hand_value(&dealer.players()[0].hands()[0], true);
-//!             // Print both
-//!             println!("Player hand value is {}", player_hand_value);
-//!             println!("Dealer hand value is {}", dealer_hand_value);
-//!             // Print whether the player won, lost, or had a push
-//!             if (player_hand_value > dealer_hand_value && player_hand_value <= 21)
-//!                 || (player_hand_value <= 21 && dealer_hand_value > 21)
-//!             {
-//!                 println!("Player Won!");
-//!             } else if player_hand_value == dealer_hand_value && player_hand_value <= 21 {
-//!                 println!("Push.");
-//!             } else {
-//!                 println!("Player Lost.");
-//!             }
-//!             PlayerAction::None
-//!         }
 //!         // Dealer returning an error with the last action
 //!         // Panic for error-checking reasons
 //!         DealerRequest::Error(_) => panic!("An error occurred"),
@@ -75,7 +55,16 @@
 //!
 //!     // Auto-play five rounds
 //!     for _ in 0..5 {
-//!         dealer.play_round(true);
+//!         let result = dealer.play_round(true);
+//!         // Print the outcome of each of the player's hands
+//!         for hand in result.players[0].hands.iter() {
+//!             match hand.outcome {
+//!                 Outcome::Win | Outcome::Blackjack => println!("Player Won ${}!", hand.net),
+//!                 Outcome::Push => println!("Push."),
+//!                 _ => println!("Player Lost ${}.", -hand.net),
+//!             }
+//!         }
+//!         println!("Dealer busted: {}", result.dealer_busted);
 //!     }
 //! }
 //!
@@ -84,6 +73,16 @@
 //! More examples are available in the examples folder of the repository.
 #![crate_name = "twentyone"]
 
+pub mod analysis;
+pub mod betting;
 pub mod cards;
+pub mod counting;
+pub mod deviations;
 pub mod game;
 pub mod prelude;
+pub mod risk;
+pub mod shoe;
+pub mod shuffle;
+pub mod side_bets;
+pub mod sim;
+pub mod strategy;
@@ -1,5 +1,7 @@
 //! Functions and structures required in almost all situations
-pub use crate::cards::{create_deck, create_shoe, shuffle_deck};
+pub use crate::cards::{create_deck, create_shoe, shuffle_deck, Card, Rank, Suit};
 pub use crate::game::{
-    Dealer, DealerRequest, GameConfig, Player, PlayerAction, PlayerActionError, DEFAULT_CONFIG,
+    Dealer, DealerRequest, Decision, GameConfig, Hand, HandValue, HoleCardRule, Outcome, Player,
+    PlayerAction, PlayerActionError, PlayerAgent, RoundResult, SurrenderRule, DEFAULT_CONFIG,
 };
+pub use crate::shoe::{Shoe, ShuffleMode};
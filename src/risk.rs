@@ -0,0 +1,269 @@
+//! Risk of ruin, N0, SCORE and hourly win rates
+//!
+//! Figures are computed analytically from the mean and variance of a round's result,
+//! or empirically by playing out bankroll trajectories from recorded rounds.
+//! Money is in the same units as the bets.
+use crate::game::RoundResult;
+use crate::sim::Statistics;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The mean and variance of the amount won in a round, with the figures computed from them
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::risk::RiskAnalysis;
+///
+/// // A player winning 0.1 units a round, with a variance of 1.3
+/// let risk = RiskAnalysis::new(0.1, 1.3);
+/// assert!((risk.n0() - 130.0).abs() < 1e-9);
+/// assert!((risk.hourly_win_rate(100.0) - 10.0).abs() < 1e-9);
+/// println!("Risk of ruin with 50 units: {:.1}%", risk.risk_of_ruin(50.0) * 100.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RiskAnalysis {
+    /// The average amount won in a round
+    pub mean: f64,
+    /// The variance of the amount won in a round
+    pub variance: f64,
+}
+
+impl RiskAnalysis {
+    /// Returns a new RiskAnalysis
+    ///
+    /// # Arguments
+    ///
+    /// * `mean` - The average amount won in a round
+    /// * `variance` - The variance of the amount won in a round
+    pub fn new(mean: f64, variance: f64) -> RiskAnalysis {
+        RiskAnalysis { mean, variance }
+    }
+
+    /// Returns a RiskAnalysis from the amounts won in each round
+    ///
+    /// # Arguments
+    ///
+    /// * `nets` - The amount won (or lost, if negative) in each round
+    pub fn from_nets(nets: &[i64]) -> RiskAnalysis {
+        let n = nets.len() as f64;
+        if nets.len() < 2 {
+            return RiskAnalysis::new(nets.first().copied().unwrap_or(0) as f64, 0.0);
+        }
+        let mean = nets.iter().sum::<i64>() as f64 / n;
+        let variance = nets
+            .iter()
+            .map(|&net| (net as f64 - mean).powi(2))
+            .sum::<f64>()
+            / (n - 1.0);
+        RiskAnalysis::new(mean, variance)
+    }
+
+    /// Returns the standard deviation of the amount won in a round
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    /// Returns the chance of ever losing a bankroll
+    ///
+    /// Players without an advantage are always ruined eventually.
+    ///
+    /// # Arguments
+    ///
+    /// * `bankroll` - The money the player starts with
+    pub fn risk_of_ruin(&self, bankroll: f64) -> f64 {
+        if self.mean <= 0.0 {
+            return 1.0;
+        }
+        if self.variance == 0.0 {
+            return 0.0;
+        }
+        (-2.0 * self.mean * bankroll / self.variance).exp()
+    }
+
+    /// Returns the chance of losing a bankroll within a number of rounds
+    ///
+    /// # Arguments
+    ///
+    /// * `bankroll` - The money the player starts with
+    /// * `rounds` - The amount of rounds played
+    pub fn risk_of_ruin_within(&self, bankroll: f64, rounds: u64) -> f64 {
+        let n = rounds as f64;
+        if rounds == 0 || self.variance == 0.0 {
+            // Without variance, the bankroll only runs out by losing on average
+            return if bankroll + self.mean * n <= 0.0 {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        let spread = self.std_dev() * n.sqrt();
+        let drift = self.mean * n;
+        let lower = normal_cdf((-bankroll - drift) / spread);
+        let upper = (-2.0 * self.mean * bankroll / self.variance).exp()
+            * normal_cdf((-bankroll + drift) / spread);
+        if upper.is_finite() {
+            (lower + upper).min(1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns N0, the amount of rounds until the expected win equals one standard deviation
+    ///
+    /// Returns infinity if the player has no advantage.
+    pub fn n0(&self) -> f64 {
+        if self.mean <= 0.0 {
+            return f64::INFINITY;
+        }
+        self.variance / (self.mean * self.mean)
+    }
+
+    /// Returns the SCORE, the expected win per 100 rounds with bets scaled
+    /// to a 10,000 unit bankroll at a 13.5% risk of ruin
+    ///
+    /// Returns zero if the player has no advantage.
+    pub fn score(&self) -> f64 {
+        if self.mean <= 0.0 {
+            return 0.0;
+        }
+        1_000_000.0 / self.n0()
+    }
+
+    /// Returns the expected win in an hour
+    ///
+    /// # Arguments
+    ///
+    /// * `rounds_per_hour` - The amount of rounds played in an hour
+    pub fn hourly_win_rate(&self, rounds_per_hour: f64) -> f64 {
+        self.mean * rounds_per_hour
+    }
+
+    /// Returns the standard deviation of the amount won in an hour
+    ///
+    /// # Arguments
+    ///
+    /// * `rounds_per_hour` - The amount of rounds played in an hour
+    pub fn hourly_std_dev(&self, rounds_per_hour: f64) -> f64 {
+        (self.variance * rounds_per_hour).sqrt()
+    }
+}
+
+/// Uses the amount won in each round of a simulation
+impl From<&Statistics> for RiskAnalysis {
+    fn from(stats: &Statistics) -> RiskAnalysis {
+        if stats.rounds < 2 {
+            return RiskAnalysis::new(stats.net as f64, 0.0);
+        }
+        let n = stats.rounds as f64;
+        let sum = stats.net as f64;
+        let variance = (stats.net_squared as f64 - sum * sum / n) / (n - 1.0);
+        RiskAnalysis::new(sum / n, variance.max(0.0))
+    }
+}
+
+/// Returns the amount won by a player in each round, skipping rounds they sat out
+///
+/// # Arguments
+///
+/// * `rounds` - The results of the rounds
+/// * `player` - The index of the player
+pub fn round_nets<'a, I: IntoIterator<Item = &'a RoundResult>>(
+    rounds: I,
+    player: usize,
+) -> Vec<i64> {
+    rounds
+        .into_iter()
+        .filter_map(|round| round.players.get(player))
+        .filter(|result| !result.hands.is_empty())
+        .map(|result| result.net() as i64)
+        .collect()
+}
+
+/// Returns the fraction of simulated bankrolls that are lost within a number of rounds
+///
+/// Each trajectory plays rounds drawn at random from the recorded results,
+/// and is ruined once the bankroll reaches zero.
+///
+/// # Arguments
+///
+/// * `nets` - The amount won (or lost, if negative) in each recorded round
+/// * `bankroll` - The money each trajectory starts with
+/// * `rounds` - The most rounds to play in each trajectory
+/// * `trials` - The amount of trajectories to play
+/// * `seed` - The seed used to draw rounds
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::game::DEFAULT_CONFIG;
+/// use twentyone::risk::{empirical_risk_of_ruin, RiskAnalysis};
+/// use twentyone::prelude::*;
+/// use twentyone::strategy::BasicStrategyAgent;
+/// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+/// #     PlayerAction::None
+/// # }
+///
+/// let mut shoe = create_shoe(6);
+/// shuffle_deck(&mut shoe);
+/// let mut dealer = Dealer::new(shoe, DEFAULT_CONFIG, &callback);
+/// let agent = BasicStrategyAgent::new(&DEFAULT_CONFIG, 10);
+/// dealer.players_mut().push(Player::with_agent(1_000_000, agent));
+/// let rounds: Vec<RoundResult> = (0..200).map(|_| dealer.play_round(true)).collect();
+///
+/// let nets = twentyone::risk::round_nets(&rounds, 0);
+/// let risk = RiskAnalysis::from_nets(&nets);
+/// let empirical = empirical_risk_of_ruin(&nets, 500, 1000, 200, 21);
+/// println!(
+///     "Risk of ruin: {:.3} analytically, {:.3} empirically",
+///     risk.risk_of_ruin_within(500.0, 1000),
+///     empirical
+/// );
+/// ```
+pub fn empirical_risk_of_ruin(
+    nets: &[i64],
+    bankroll: i64,
+    rounds: u64,
+    trials: u32,
+    seed: u64,
+) -> f64 {
+    if nets.is_empty() || trials == 0 {
+        return if bankroll <= 0 { 1.0 } else { 0.0 };
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut ruined = 0;
+    for _ in 0..trials {
+        let mut money = bankroll;
+        for _ in 0..rounds {
+            if money <= 0 {
+                break;
+            }
+            money += nets[rng.gen_range(0..nets.len())];
+        }
+        if money <= 0 {
+            ruined += 1;
+        }
+    }
+    ruined as f64 / trials as f64
+}
+
+/// Returns the standard normal cumulative distribution function
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+/// Returns the error function, accurate to about 1.5e-7
+fn erf(x: f64) -> f64 {
+    // Abramowitz and Stegun formula 7.1.26
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x < 0.0 {
+        -y
+    } else {
+        y
+    }
+}
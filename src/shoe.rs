@@ -0,0 +1,303 @@
+//! A dealing shoe with a cut card, burn cards and a discard tray
+use crate::cards::{self, Card, ParseCardError};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// How used cards are returned to a shoe
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShuffleMode {
+    /// The shoe is shuffled by hand once the cut card comes out
+    CutCard,
+    /// A continuous shuffling machine (CSM), where the discard tray
+    /// goes back into the shoe after every round
+    Continuous {
+        /// Whether discards are inserted at random positions
+        /// instead of reshuffling the whole shoe
+        random_positions: bool,
+    },
+}
+
+/// Describes a dealing shoe
+///
+/// Cards are drawn from the front of the shoe in constant time.
+/// Used cards are put in the discard tray, and are only shuffled back into the shoe
+/// when the shoe is shuffled.
+///
+/// A cut card is placed in the shoe at a fraction of its cards (the penetration).
+/// Once it comes out, the shoe should be shuffled before the next round.
+/// Shoes in a continuous shuffling machine have their discards reinserted after every round
+/// instead (see [`ShuffleMode`]).
+///
+/// # Examples
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use twentyone::shoe::Shoe;
+///
+/// let mut rng = StdRng::seed_from_u64(21);
+/// let mut shoe = Shoe::new(6);
+/// shoe.set_penetration(0.75);
+/// shoe.shuffle(&mut rng);
+/// shoe.burn(1);
+///
+/// let card = shoe.draw().unwrap();
+/// shoe.discard(vec![card]);
+/// assert_eq!(shoe.dealt(), 2);
+/// assert_eq!(shoe.remaining(), 310);
+/// assert!(!shoe.cut_card_reached());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shoe {
+    cards: VecDeque<Card>,
+    discards: Vec<Card>,
+    penetration: f32,
+    cut_card: usize,
+    dealt: usize,
+    mode: ShuffleMode,
+}
+
+impl Shoe {
+    /// Returns a new unshuffled shoe with a specified amount of decks in it
+    ///
+    /// The cut card is placed at the end of the shoe.
+    ///
+    /// # Arguments
+    ///
+    /// * `deck_count` - The amount of decks to be placed in the shoe
+    pub fn new(deck_count: u8) -> Shoe {
+        Shoe::from(cards::create_shoe(deck_count))
+    }
+
+    /// Returns the cards left in the shoe, in the order they will be drawn
+    pub fn cards(&self) -> &VecDeque<Card> {
+        &self.cards
+    }
+
+    /// Returns the cards in the discard tray
+    pub fn discards(&self) -> &Vec<Card> {
+        &self.discards
+    }
+
+    /// Returns the amount of cards left in the shoe
+    pub fn remaining(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Returns the amount of cards drawn or burned since the shoe was last shuffled
+    pub fn dealt(&self) -> usize {
+        self.dealt
+    }
+
+    /// Returns the amount of cards in the shoe, including those dealt since it was last shuffled
+    pub fn len(&self) -> usize {
+        self.dealt + self.cards.len()
+    }
+
+    /// Returns whether the shoe has no cards, including those dealt since it was last shuffled
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the fraction of the shoe that is dealt before the cut card comes out
+    pub fn penetration(&self) -> f32 {
+        self.penetration
+    }
+
+    /// Returns whether the cut card has come out
+    ///
+    /// The cut card never comes out of continuous shoes.
+    pub fn cut_card_reached(&self) -> bool {
+        self.mode == ShuffleMode::CutCard && self.dealt >= self.cut_card
+    }
+
+    /// Returns how used cards are returned to the shoe
+    pub fn mode(&self) -> ShuffleMode {
+        self.mode
+    }
+
+    /// Set how used cards are returned to the shoe
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The shuffling mode to use
+    pub fn set_mode(&mut self, mode: ShuffleMode) {
+        self.mode = mode;
+    }
+
+    /// Place the cut card at a fraction of the shoe
+    ///
+    /// # Arguments
+    ///
+    /// * `penetration` - The fraction of the shoe to deal before the cut card comes out,
+    ///   from `0.0` to `1.0`
+    pub fn set_penetration(&mut self, penetration: f32) {
+        self.penetration = penetration.clamp(0.0, 1.0);
+        self.cut_card = (self.len() as f32 * self.penetration) as usize;
+    }
+
+    /// Draw the next card from the shoe
+    ///
+    /// Returns `None` if the shoe is empty.
+    pub fn draw(&mut self) -> Option<Card> {
+        let card = self.cards.pop_front()?;
+        self.dealt += 1;
+        Some(card)
+    }
+
+    /// Draw cards from the shoe and put them in the discard tray without showing them
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The amount of cards to burn
+    pub fn burn(&mut self, count: usize) {
+        for _ in 0..count {
+            match self.draw() {
+                Some(card) => self.discards.push(card),
+                None => break,
+            }
+        }
+    }
+
+    /// Put cards on top of the shoe, so that they are drawn next in the given order
+    ///
+    /// Each card is taken out of the rest of the shoe or the discard tray,
+    /// so that no card is in two places at once.
+    /// Cards that are in neither (eg. cards still on the table, or more copies
+    /// of a card than the shoe has) are added to the shoe.
+    ///
+    /// # Arguments
+    ///
+    /// * `cards` - The cards to put on top, in the order they will be drawn
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::shoe::Shoe;
+    /// let mut shoe = Shoe::new(1);
+    /// shoe.stack(vec!["8D".parse().unwrap(), "8C".parse().unwrap()]);
+    /// assert_eq!(shoe.draw(), Some("8D".parse().unwrap()));
+    /// assert_eq!(shoe.draw(), Some("8C".parse().unwrap()));
+    /// assert_eq!(shoe.len(), 52);
+    /// ```
+    pub fn stack<I: IntoIterator<Item = Card>>(&mut self, cards: I) {
+        let cards: Vec<Card> = cards.into_iter().collect();
+        for card in cards.iter() {
+            if let Some(position) = self.cards.iter().position(|other| other == card) {
+                self.cards.remove(position);
+            } else if let Some(position) = self.discards.iter().position(|other| other == card) {
+                // The card goes back in the shoe, so it no longer counts as dealt
+                self.discards.remove(position);
+                self.dealt = self.dealt.saturating_sub(1);
+            }
+        }
+        for &card in cards.iter().rev() {
+            self.cards.push_front(card);
+        }
+    }
+
+    /// Put used cards in the discard tray
+    ///
+    /// # Arguments
+    ///
+    /// * `cards` - The cards to discard
+    pub fn discard<I: IntoIterator<Item = Card>>(&mut self, cards: I) {
+        self.discards.extend(cards);
+    }
+
+    /// Put the discard tray back in the shoe and shuffle it
+    ///
+    /// The cut card is placed again using the shoe's penetration.
+    /// Cards that were drawn but not discarded (eg. cards still on the table)
+    /// are not put back in the shoe.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The random number generator to shuffle with
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.extend(self.discards.drain(..));
+        self.cards.make_contiguous().shuffle(rng);
+        self.dealt = 0;
+        self.set_penetration(self.penetration);
+    }
+
+    /// Put the discard tray back in the shoe, as a continuous shuffling machine would
+    ///
+    /// Continuous shoes with `random_positions` (and cut card shoes) have each discard
+    /// inserted at a random position. Other continuous shoes are reshuffled completely.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - The random number generator to shuffle with
+    pub fn reinsert_discards<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        if self.mode
+            == (ShuffleMode::Continuous {
+                random_positions: false,
+            })
+        {
+            self.shuffle(rng);
+            return;
+        }
+
+        for card in self.discards.drain(..) {
+            let position = rng.gen_range(0..=self.cards.len());
+            self.cards.insert(position, card);
+        }
+        self.dealt = 0;
+        self.set_penetration(self.penetration);
+    }
+}
+
+/// Creates a shoe that deals cards in the same order as the vector,
+/// with the cut card placed at the end
+impl From<Vec<Card>> for Shoe {
+    fn from(cards: Vec<Card>) -> Shoe {
+        let cut_card = cards.len();
+        Shoe {
+            cards: cards.into(),
+            discards: Vec::new(),
+            penetration: 1.0,
+            cut_card,
+            dealt: 0,
+            mode: ShuffleMode::CutCard,
+        }
+    }
+}
+
+/// Parses a shoe that deals cards in the order they are written (eg. `"AS KH 8D 8C"`),
+/// with the cut card placed at the end
+///
+/// See [`cards::parse_cards`] for the format.
+impl FromStr for Shoe {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Shoe, ParseCardError> {
+        Ok(Shoe::from(cards::parse_cards(s)?))
+    }
+}
+
+/// Returns an unshuffled shoe with a specified amount of decks in it and a shuffling mode
+///
+/// # Arguments
+///
+/// * `deck_count` - The amount of decks to be placed in the shoe
+/// * `mode` - How used cards are returned to the shoe
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::shoe::{self, ShuffleMode};
+/// let csm = shoe::create_shoe(
+///     6,
+///     ShuffleMode::Continuous {
+///         random_positions: true,
+///     },
+/// );
+/// ```
+pub fn create_shoe(deck_count: u8, mode: ShuffleMode) -> Shoe {
+    let mut shoe = Shoe::new(deck_count);
+    shoe.set_mode(mode);
+    shoe
+}
@@ -0,0 +1,264 @@
+//! Shuffles that model real dealers, such as riffles, strips, box shuffles and cuts
+//!
+//! Unlike [`cards::shuffle_deck`](crate::cards::shuffle_deck), these shuffles are not uniform.
+//! A few of them in a row leave clumps of cards together, as a hand-shuffled shoe would.
+use crate::cards::Card;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// A single step of a shuffle procedure
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShuffleStep {
+    /// A riffle shuffle (see [`riffle`])
+    Riffle,
+    /// A strip shuffle (see [`strip`])
+    Strip {
+        /// The amount of packets to strip off
+        packets: usize,
+    },
+    /// A box shuffle (see [`box_shuffle`])
+    Box {
+        /// The amount of packets to split the cards into
+        packets: usize,
+    },
+    /// A cut (see [`cut`])
+    Cut,
+}
+
+/// A sequence of shuffles performed by a dealer
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards;
+/// use twentyone::shuffle::{ShuffleProcedure, ShuffleStep};
+///
+/// // Riffle twice, then cut
+/// let procedure = ShuffleProcedure::new(vec![
+///     ShuffleStep::Riffle,
+///     ShuffleStep::Riffle,
+///     ShuffleStep::Cut,
+/// ]);
+/// let mut first = cards::create_shoe(2);
+/// let mut second = cards::create_shoe(2);
+/// procedure.apply_seeded(&mut first, 21);
+/// procedure.apply_seeded(&mut second, 21);
+/// assert_eq!(first, second);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShuffleProcedure {
+    /// The steps of the procedure, in the order they are performed
+    pub steps: Vec<ShuffleStep>,
+}
+
+impl ShuffleProcedure {
+    /// Returns a new ShuffleProcedure
+    ///
+    /// # Arguments
+    ///
+    /// * `steps` - The steps of the procedure, in the order they are performed
+    pub fn new(steps: Vec<ShuffleStep>) -> ShuffleProcedure {
+        ShuffleProcedure { steps }
+    }
+
+    /// Returns the common casino procedure of riffle, riffle, strip, riffle, cut
+    pub fn riffle_riffle_strip_riffle_cut() -> ShuffleProcedure {
+        ShuffleProcedure::new(vec![
+            ShuffleStep::Riffle,
+            ShuffleStep::Riffle,
+            ShuffleStep::Strip { packets: 4 },
+            ShuffleStep::Riffle,
+            ShuffleStep::Cut,
+        ])
+    }
+
+    /// Returns a procedure of seven riffles followed by a cut,
+    /// which is close to a uniform shuffle for a single deck
+    pub fn seven_riffles() -> ShuffleProcedure {
+        let mut steps = vec![ShuffleStep::Riffle; 7];
+        steps.push(ShuffleStep::Cut);
+        ShuffleProcedure::new(steps)
+    }
+
+    /// Returns a procedure of a box shuffle between two riffles, followed by a cut
+    pub fn riffle_box_riffle_cut() -> ShuffleProcedure {
+        ShuffleProcedure::new(vec![
+            ShuffleStep::Riffle,
+            ShuffleStep::Box { packets: 4 },
+            ShuffleStep::Riffle,
+            ShuffleStep::Cut,
+        ])
+    }
+
+    /// Perform every step of the procedure on a deck or shoe
+    ///
+    /// # Arguments
+    ///
+    /// * `deck` - The deck or shoe to shuffle
+    /// * `rng` - The random number generator to shuffle with
+    pub fn apply<R: Rng + ?Sized>(&self, deck: &mut [Card], rng: &mut R) {
+        for step in self.steps.iter() {
+            match *step {
+                ShuffleStep::Riffle => riffle(deck, rng),
+                ShuffleStep::Strip { packets } => strip(deck, packets, rng),
+                ShuffleStep::Box { packets } => box_shuffle(deck, packets, rng),
+                ShuffleStep::Cut => cut(deck, rng),
+            }
+        }
+    }
+
+    /// Perform every step of the procedure on a deck or shoe with a seed
+    ///
+    /// The same seed will always produce the same order.
+    ///
+    /// # Arguments
+    ///
+    /// * `deck` - The deck or shoe to shuffle
+    /// * `seed` - The seed to shuffle with
+    pub fn apply_seeded(&self, deck: &mut [Card], seed: u64) {
+        self.apply(deck, &mut StdRng::seed_from_u64(seed));
+    }
+}
+
+/// Returns a position near the middle of `len` cards, as a dealer would cut by eye
+///
+/// The position is binomially distributed, as in the Gilbert–Shannon–Reeds model.
+fn cut_position<R: Rng + ?Sized>(len: usize, rng: &mut R) -> usize {
+    (0..len).filter(|_| rng.gen_bool(0.5)).count()
+}
+
+/// Riffle shuffle a deck or shoe using the Gilbert–Shannon–Reeds model
+///
+/// The cards are cut near the middle, then the two halves are riffled together,
+/// with each card dropping from a half with a chance proportional to that half's size.
+///
+/// # Arguments
+///
+/// * `deck` - The deck or shoe to shuffle
+/// * `rng` - The random number generator to shuffle with
+///
+/// # Examples
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use twentyone::{cards, shuffle};
+/// let mut deck = cards::create_deck();
+/// shuffle::riffle(&mut deck, &mut StdRng::seed_from_u64(21));
+/// ```
+pub fn riffle<R: Rng + ?Sized>(deck: &mut [Card], rng: &mut R) {
+    let split = cut_position(deck.len(), rng);
+    let (mut left, mut right) = (deck[..split].iter(), deck[split..].iter());
+    let mut riffled = Vec::with_capacity(deck.len());
+    while left.len() + right.len() > 0 {
+        let from_left = rng.gen_range(0..left.len() + right.len()) < left.len();
+        let card = if from_left { left.next() } else { right.next() };
+        riffled.push(*card.unwrap());
+    }
+    deck.copy_from_slice(&riffled);
+}
+
+/// Strip shuffle a deck or shoe
+///
+/// Packets of random sizes are pulled off the top one at a time and stacked on each other,
+/// reversing the order of the packets but not the cards within them.
+///
+/// # Arguments
+///
+/// * `deck` - The deck or shoe to shuffle
+/// * `packets` - The amount of packets to strip off
+/// * `rng` - The random number generator to shuffle with
+///
+/// # Examples
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use twentyone::{cards, shuffle};
+/// let mut deck = cards::create_deck();
+/// shuffle::strip(&mut deck, 4, &mut StdRng::seed_from_u64(21));
+/// ```
+pub fn strip<R: Rng + ?Sized>(deck: &mut [Card], packets: usize, rng: &mut R) {
+    if deck.is_empty() || packets < 2 {
+        return;
+    }
+
+    // Choose where each packet ends
+    let mut ends: Vec<usize> = (1..packets)
+        .map(|_| rng.gen_range(0..=deck.len()))
+        .collect();
+    ends.push(deck.len());
+    ends.sort_unstable();
+    reverse_packets(deck, &ends);
+}
+
+/// Box shuffle a deck or shoe
+///
+/// The cards are split into packets of roughly equal size,
+/// then the order of the packets is reversed.
+///
+/// # Arguments
+///
+/// * `deck` - The deck or shoe to shuffle
+/// * `packets` - The amount of packets to split the cards into
+/// * `rng` - The random number generator to shuffle with
+///
+/// # Examples
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use twentyone::{cards, shuffle};
+/// let mut deck = cards::create_deck();
+/// shuffle::box_shuffle(&mut deck, 4, &mut StdRng::seed_from_u64(21));
+/// ```
+pub fn box_shuffle<R: Rng + ?Sized>(deck: &mut [Card], packets: usize, rng: &mut R) {
+    if deck.is_empty() || packets < 2 {
+        return;
+    }
+
+    // Each packet is cut by eye, so it may be a few cards off from an even split
+    let size = deck.len() / packets;
+    let slack = size / 4;
+    let mut ends = Vec::with_capacity(packets);
+    for i in 1..packets {
+        let end = (i * size + rng.gen_range(0..=slack * 2)).saturating_sub(slack);
+        ends.push(end.min(deck.len()));
+    }
+    ends.push(deck.len());
+    ends.sort_unstable();
+    reverse_packets(deck, &ends);
+}
+
+/// Cut a deck or shoe near the middle, moving the top part to the bottom
+///
+/// # Arguments
+///
+/// * `deck` - The deck or shoe to cut
+/// * `rng` - The random number generator to choose the cut with
+///
+/// # Examples
+///
+/// ```
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use twentyone::{cards, shuffle};
+/// let mut deck = cards::create_deck();
+/// shuffle::cut(&mut deck, &mut StdRng::seed_from_u64(21));
+/// ```
+pub fn cut<R: Rng + ?Sized>(deck: &mut [Card], rng: &mut R) {
+    let position = cut_position(deck.len(), rng);
+    deck.rotate_left(position);
+}
+
+/// Reverse the order of packets in a deck, keeping the order of cards within them
+///
+/// `ends` must be sorted and end with the length of the deck.
+fn reverse_packets(deck: &mut [Card], ends: &[usize]) {
+    let mut reversed = Vec::with_capacity(deck.len());
+    for (i, &end) in ends.iter().enumerate().rev() {
+        let start = if i == 0 { 0 } else { ends[i - 1] };
+        reversed.extend_from_slice(&deck[start..end]);
+    }
+    deck.copy_from_slice(&reversed);
+}
@@ -0,0 +1,333 @@
+//! Common side bets with configurable paytables
+//!
+//! Each side bet's paytable is made of public fields, given as odds to 1,
+//! and starts out with a common casino paytable.
+//! Side bets are offered at a table with [`Dealer::add_side_bet`](crate::game::Dealer::add_side_bet).
+use crate::cards::{Card, Rank, Suit};
+use crate::game::{HandValue, SideBet, SideBetKind, SideBetOutcome};
+
+/// Pays when the player's first two cards are a pair
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::parse_cards;
+/// use twentyone::game::{SideBet, SideBetOutcome};
+/// use twentyone::side_bets::PerfectPairs;
+///
+/// let perfect_pairs = PerfectPairs::new();
+/// let hand = parse_cards("8H 8D").unwrap();
+/// let up_card = "6S".parse().unwrap();
+/// assert_eq!(perfect_pairs.settle_on_deal(&hand, up_card), Some(SideBetOutcome::Win(12)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PerfectPairs {
+    /// The payout for a pair of the same suit
+    pub perfect: u32,
+    /// The payout for a pair of the same colour, but different suits
+    pub colored: u32,
+    /// The payout for a pair of different colours
+    pub mixed: u32,
+}
+
+/// Pays when the player's first two cards and the dealer's up card make a three card poker hand
+///
+/// Aces count as high or low for straights.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::parse_cards;
+/// use twentyone::game::{SideBet, SideBetOutcome};
+/// use twentyone::side_bets::TwentyOnePlusThree;
+///
+/// let twenty_one_plus_three = TwentyOnePlusThree::new();
+/// let hand = parse_cards("QH KD").unwrap();
+/// let up_card = "AS".parse().unwrap();
+/// assert_eq!(
+///     twenty_one_plus_three.settle_on_deal(&hand, up_card),
+///     Some(SideBetOutcome::Win(10))
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TwentyOnePlusThree {
+    /// The payout for three of a kind of the same suit
+    pub suited_trips: u32,
+    /// The payout for a straight of the same suit
+    pub straight_flush: u32,
+    /// The payout for three of a kind
+    pub three_of_a_kind: u32,
+    /// The payout for a straight
+    pub straight: u32,
+    /// The payout for three cards of the same suit
+    pub flush: u32,
+}
+
+/// Pays when the player's first two cards total 20
+///
+/// A pair of queens of hearts waits for the dealer's final hand,
+/// since it pays more if the dealer has blackjack.
+/// Every other hand is settled on the deal.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::parse_cards;
+/// use twentyone::game::{SideBet, SideBetOutcome};
+/// use twentyone::side_bets::LuckyLadies;
+///
+/// let lucky_ladies = LuckyLadies::new();
+/// let hand = parse_cards("QH QH").unwrap();
+/// let dealer_hand = parse_cards("AS KD").unwrap();
+/// assert_eq!(lucky_ladies.settle_on_deal(&hand, dealer_hand[0]), None);
+/// assert_eq!(
+///     lucky_ladies.settle_on_dealer_final(&hand, &dealer_hand),
+///     SideBetOutcome::Win(1000)
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LuckyLadies {
+    /// The payout for a pair of queens of hearts when the dealer has blackjack
+    pub queen_hearts_dealer_blackjack: u32,
+    /// The payout for a pair of queens of hearts
+    pub queen_hearts: u32,
+    /// The payout for a 20 made of two cards of the same rank and suit
+    pub matched: u32,
+    /// The payout for a 20 made of two cards of the same suit
+    pub suited: u32,
+    /// The payout for any other 20
+    pub any: u32,
+}
+
+/// Pays when the dealer busts, with more paid the more cards are in the dealer's hand
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::cards::parse_cards;
+/// use twentyone::game::{SideBet, SideBetOutcome};
+/// use twentyone::side_bets::Buster;
+///
+/// let buster = Buster::new();
+/// let hand = parse_cards("TH 7D").unwrap();
+/// let dealer_hand = parse_cards("2S 3C 4D 5H TS").unwrap();
+/// assert_eq!(buster.settle_on_dealer_final(&hand, &dealer_hand), SideBetOutcome::Win(9));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Buster {
+    /// The payouts for a dealer bust with three, four, five, six, seven,
+    /// and eight or more cards
+    pub payouts: [u32; 6],
+}
+
+impl PerfectPairs {
+    /// Returns a Perfect Pairs side bet paying 25 to 1 for a perfect pair,
+    /// 12 to 1 for a colored pair and 6 to 1 for a mixed pair
+    pub fn new() -> PerfectPairs {
+        PerfectPairs {
+            perfect: 25,
+            colored: 12,
+            mixed: 6,
+        }
+    }
+}
+
+impl TwentyOnePlusThree {
+    /// Returns a 21+3 side bet paying 100 to 1 for suited trips, 40 to 1 for a straight flush,
+    /// 30 to 1 for three of a kind, 10 to 1 for a straight and 5 to 1 for a flush
+    pub fn new() -> TwentyOnePlusThree {
+        TwentyOnePlusThree {
+            suited_trips: 100,
+            straight_flush: 40,
+            three_of_a_kind: 30,
+            straight: 10,
+            flush: 5,
+        }
+    }
+}
+
+impl LuckyLadies {
+    /// Returns a Lucky Ladies side bet paying 1000 to 1 for a pair of queens of hearts
+    /// against a dealer blackjack, 125 to 1 for a pair of queens of hearts,
+    /// 19 to 1 for a matched 20, 9 to 1 for a suited 20 and 4 to 1 for any 20
+    pub fn new() -> LuckyLadies {
+        LuckyLadies {
+            queen_hearts_dealer_blackjack: 1000,
+            queen_hearts: 125,
+            matched: 19,
+            suited: 9,
+            any: 4,
+        }
+    }
+}
+
+impl Buster {
+    /// Returns a Buster Blackjack side bet paying 1 to 1 for a three card bust,
+    /// 2 to 1 for four cards, 9 to 1 for five cards, 50 to 1 for six cards,
+    /// 100 to 1 for seven cards and 250 to 1 for eight or more cards
+    pub fn new() -> Buster {
+        Buster {
+            payouts: [1, 2, 9, 50, 100, 250],
+        }
+    }
+}
+
+impl Default for PerfectPairs {
+    fn default() -> PerfectPairs {
+        PerfectPairs::new()
+    }
+}
+
+impl Default for TwentyOnePlusThree {
+    fn default() -> TwentyOnePlusThree {
+        TwentyOnePlusThree::new()
+    }
+}
+
+impl Default for LuckyLadies {
+    fn default() -> LuckyLadies {
+        LuckyLadies::new()
+    }
+}
+
+impl Default for Buster {
+    fn default() -> Buster {
+        Buster::new()
+    }
+}
+
+impl SideBet for PerfectPairs {
+    fn kind(&self) -> SideBetKind {
+        SideBetKind::PerfectPairs
+    }
+
+    fn settle_on_deal(&self, hand: &[Card], _up_card: Card) -> Option<SideBetOutcome> {
+        let (a, b) = match first_two(hand) {
+            Some(cards) => cards,
+            None => return Some(SideBetOutcome::Loss),
+        };
+        let outcome = if a.rank != b.rank {
+            SideBetOutcome::Loss
+        } else if a.suit == b.suit {
+            SideBetOutcome::Win(self.perfect)
+        } else if is_red(a.suit) == is_red(b.suit) {
+            SideBetOutcome::Win(self.colored)
+        } else {
+            SideBetOutcome::Win(self.mixed)
+        };
+        Some(outcome)
+    }
+}
+
+impl SideBet for TwentyOnePlusThree {
+    fn kind(&self) -> SideBetKind {
+        SideBetKind::TwentyOnePlusThree
+    }
+
+    fn settle_on_deal(&self, hand: &[Card], up_card: Card) -> Option<SideBetOutcome> {
+        let (a, b) = match first_two(hand) {
+            Some(cards) => cards,
+            None => return Some(SideBetOutcome::Loss),
+        };
+        let cards = [a, b, up_card];
+        let flush = cards.iter().all(|card| card.suit == cards[0].suit);
+        let trips = cards.iter().all(|card| card.rank == cards[0].rank);
+
+        let mut ranks = [cards[0].rank, cards[1].rank, cards[2].rank];
+        ranks.sort();
+        let consecutive = |ranks: [Rank; 3]| {
+            ranks[1] as u8 == ranks[0] as u8 + 1 && ranks[2] as u8 == ranks[1] as u8 + 1
+        };
+        // Aces can also be low, below a two and a three
+        let straight = consecutive(ranks) || ranks == [Rank::Two, Rank::Three, Rank::Ace];
+
+        let outcome = if trips && flush {
+            SideBetOutcome::Win(self.suited_trips)
+        } else if straight && flush {
+            SideBetOutcome::Win(self.straight_flush)
+        } else if trips {
+            SideBetOutcome::Win(self.three_of_a_kind)
+        } else if straight {
+            SideBetOutcome::Win(self.straight)
+        } else if flush {
+            SideBetOutcome::Win(self.flush)
+        } else {
+            SideBetOutcome::Loss
+        };
+        Some(outcome)
+    }
+}
+
+impl SideBet for LuckyLadies {
+    fn kind(&self) -> SideBetKind {
+        SideBetKind::LuckyLadies
+    }
+
+    fn settle_on_deal(&self, hand: &[Card], _up_card: Card) -> Option<SideBetOutcome> {
+        let (a, b) = match first_two(hand) {
+            Some(cards) => cards,
+            None => return Some(SideBetOutcome::Loss),
+        };
+        let outcome = if HandValue::new(&[a, b]).total != 20 {
+            SideBetOutcome::Loss
+        } else if is_queen_of_hearts_pair(hand) {
+            // Wait to see if the dealer has blackjack
+            return None;
+        } else if a == b {
+            SideBetOutcome::Win(self.matched)
+        } else if a.suit == b.suit {
+            SideBetOutcome::Win(self.suited)
+        } else {
+            SideBetOutcome::Win(self.any)
+        };
+        Some(outcome)
+    }
+
+    fn settle_on_dealer_final(&self, hand: &[Card], dealer_hand: &[Card]) -> SideBetOutcome {
+        // Only a pair of queens of hearts waits for the dealer's hand
+        if !is_queen_of_hearts_pair(hand) {
+            SideBetOutcome::Loss
+        } else if HandValue::new(dealer_hand).is_blackjack {
+            SideBetOutcome::Win(self.queen_hearts_dealer_blackjack)
+        } else {
+            SideBetOutcome::Win(self.queen_hearts)
+        }
+    }
+}
+
+impl SideBet for Buster {
+    fn kind(&self) -> SideBetKind {
+        SideBetKind::Buster
+    }
+
+    fn settle_on_dealer_final(&self, _hand: &[Card], dealer_hand: &[Card]) -> SideBetOutcome {
+        if !HandValue::new(dealer_hand).is_bust {
+            return SideBetOutcome::Loss;
+        }
+        // The dealer needs at least three cards to bust
+        let i = dealer_hand.len().clamp(3, 8) - 3;
+        SideBetOutcome::Win(self.payouts[i])
+    }
+}
+
+/// Returns whether a suit is red
+fn is_red(suit: Suit) -> bool {
+    suit == Suit::Hearts || suit == Suit::Diamonds
+}
+
+/// Returns the first two cards of a hand, or `None` if it has fewer than two cards
+fn first_two(hand: &[Card]) -> Option<(Card, Card)> {
+    match hand {
+        [a, b, ..] => Some((*a, *b)),
+        _ => None,
+    }
+}
+
+/// Returns whether the first two cards of a hand are both the queen of hearts
+fn is_queen_of_hearts_pair(hand: &[Card]) -> bool {
+    let is_queen_of_hearts = |card: Card| card.rank == Rank::Queen && card.suit == Suit::Hearts;
+    match first_two(hand) {
+        Some((a, b)) => is_queen_of_hearts(a) && is_queen_of_hearts(b),
+        None => false,
+    }
+}
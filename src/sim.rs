@@ -0,0 +1,362 @@
+//! Monte Carlo simulation of many rounds with aggregate statistics
+//!
+//! Simulations play rounds with a single player and no output,
+//! and are reproducible from a seed.
+//! Long simulations can be split across threads with [`simulate_parallel`],
+//! and bankroll-dependent betting can be played out with [`simulate_with_bankroll`].
+use crate::game::{
+    Dealer, DealerRequest, GameConfig, Outcome, Player, PlayerAction, PlayerAgent, PlayerResult,
+};
+use crate::shoe::Shoe;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::thread;
+
+/// The money the simulated player is topped up to before every round,
+/// so that a simulation never runs out of money
+const BANKROLL: i32 = i32::MAX / 2;
+
+/// Statistics gathered from a simulation
+///
+/// Outcome counts are per hand, so split hands are counted separately.
+/// Money is counted per round, including insurance and side bets.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// The amount of rounds played, not including rounds sat out
+    pub rounds: u64,
+    /// The amount of hands played, including split hands
+    pub hands: u64,
+    /// The amount of hands that won, not including blackjacks
+    pub wins: u64,
+    /// The amount of hands that lost without busting
+    pub losses: u64,
+    /// The amount of hands that pushed
+    pub pushes: u64,
+    /// The amount of hands paid as a blackjack
+    pub blackjacks: u64,
+    /// The amount of hands surrendered
+    pub surrenders: u64,
+    /// The amount of hands that busted
+    pub busts: u64,
+    /// The total of the original bets of every round
+    pub wagered: i64,
+    /// The total amount won (or lost, if negative)
+    pub net: i64,
+    /// The sum of the square of the amount won in each round, used for the variance
+    pub net_squared: u128,
+    /// The amount of simulations that ended with the player unable to afford the minimum bet
+    pub ruins: u64,
+}
+
+impl Statistics {
+    /// Returns new, empty Statistics
+    pub fn new() -> Statistics {
+        Statistics::default()
+    }
+
+    /// Add the result of a round
+    ///
+    /// Rounds the player sat out are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The player's result for the round
+    pub fn record(&mut self, result: &PlayerResult) {
+        // Rounds the player sat out aren't counted
+        if result.hands.is_empty() {
+            return;
+        }
+        self.rounds += 1;
+        for hand in result.hands.iter() {
+            self.hands += 1;
+            match hand.outcome {
+                Outcome::Win => self.wins += 1,
+                Outcome::Loss => self.losses += 1,
+                Outcome::Push => self.pushes += 1,
+                Outcome::Blackjack => self.blackjacks += 1,
+                Outcome::Surrender => self.surrenders += 1,
+                Outcome::Bust => self.busts += 1,
+            }
+        }
+        let net = result.net() as i64;
+        self.wagered += result.bet as i64;
+        self.net += net;
+        // Square in a wider type so that large results can't overflow
+        let net = net as i128;
+        self.net_squared += (net * net) as u128;
+    }
+
+    /// Add the statistics of another simulation
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The statistics to add
+    pub fn merge(&mut self, other: &Statistics) {
+        self.rounds += other.rounds;
+        self.hands += other.hands;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.pushes += other.pushes;
+        self.blackjacks += other.blackjacks;
+        self.surrenders += other.surrenders;
+        self.busts += other.busts;
+        self.wagered += other.wagered;
+        self.net += other.net;
+        self.net_squared += other.net_squared;
+        self.ruins += other.ruins;
+    }
+
+    /// Returns the expected value per round, as a fraction of the original bet
+    ///
+    /// A negative value is the house edge.
+    pub fn ev(&self) -> f64 {
+        if self.wagered == 0 {
+            return 0.0;
+        }
+        self.net as f64 / self.wagered as f64
+    }
+
+    /// Returns the standard deviation of the amount won in a round,
+    /// in units of the average original bet
+    pub fn std_dev(&self) -> f64 {
+        if self.rounds < 2 || self.wagered == 0 {
+            return 0.0;
+        }
+        let n = self.rounds as f64;
+        let sum = self.net as f64;
+        let variance = (self.net_squared as f64 - sum * sum / n) / (n - 1.0);
+        variance.max(0.0).sqrt() / (self.wagered as f64 / n)
+    }
+
+    /// Returns a confidence interval for the expected value as a `(low, high)` pair
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The amount of standard errors on each side (eg. `1.96` for 95% confidence)
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        let error = if self.rounds == 0 {
+            0.0
+        } else {
+            z * self.std_dev() / (self.rounds as f64).sqrt()
+        };
+        (self.ev() - error, self.ev() + error)
+    }
+
+    /// Returns the fraction of hands that won, including blackjacks
+    pub fn win_rate(&self) -> f64 {
+        self.rate(self.wins + self.blackjacks)
+    }
+
+    /// Returns the fraction of hands that lost, including busts and surrenders
+    pub fn loss_rate(&self) -> f64 {
+        self.rate(self.losses + self.busts + self.surrenders)
+    }
+
+    /// Returns the fraction of hands that pushed
+    pub fn push_rate(&self) -> f64 {
+        self.rate(self.pushes)
+    }
+
+    /// Returns the fraction of hands paid as a blackjack
+    pub fn blackjack_rate(&self) -> f64 {
+        self.rate(self.blackjacks)
+    }
+
+    fn rate(&self, count: u64) -> f64 {
+        if self.hands == 0 {
+            return 0.0;
+        }
+        count as f64 / self.hands as f64
+    }
+}
+
+/// The dealer's callback for simulations, which ignores every event
+fn silent(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+    PlayerAction::None
+}
+
+/// Play rounds with a single player and return the statistics
+///
+/// A new shoe is created from the game's rules and shuffled with the seed,
+/// so the same agent, rules and seed always give the same statistics.
+/// The player is given as much money as they need,
+/// so agents that bet based on their bankroll should use [`simulate_with_bankroll`] instead.
+///
+/// # Arguments
+///
+/// * `config` - The rules of the game
+/// * `agent` - The agent that plays the player's hands
+/// * `rounds` - The amount of rounds to deal, including any the player sits out
+/// * `seed` - The seed used to shuffle the shoe
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::game::DEFAULT_CONFIG;
+/// use twentyone::sim::simulate;
+/// use twentyone::strategy::BasicStrategyAgent;
+///
+/// let agent = BasicStrategyAgent::new(&DEFAULT_CONFIG, 10);
+/// let stats = simulate(&DEFAULT_CONFIG, agent, 1000, 21);
+/// assert_eq!(stats.rounds, 1000);
+///
+/// let (low, high) = stats.confidence_interval(1.96);
+/// println!("EV: {:.4} ({:.4} to {:.4})", stats.ev(), low, high);
+/// ```
+pub fn simulate<A: PlayerAgent + 'static>(
+    config: &GameConfig,
+    agent: A,
+    rounds: u64,
+    seed: u64,
+) -> Statistics {
+    run(config, agent, rounds, seed, None)
+}
+
+/// Play rounds with a single player starting with a real bankroll and return the statistics
+///
+/// Unlike [`simulate`], the player's money carries over from round to round,
+/// so agents that bet based on their bankroll (eg. with [`Kelly`](crate::betting::Kelly))
+/// see the money they actually have.
+/// The simulation ends early if the player can no longer afford the minimum bet,
+/// which is counted in [`Statistics::ruins`].
+///
+/// # Arguments
+///
+/// * `config` - The rules of the game
+/// * `agent` - The agent that plays the player's hands
+/// * `rounds` - The most rounds to deal, including any the player sits out
+/// * `seed` - The seed used to shuffle the shoe
+/// * `bankroll` - The money the player starts with
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::betting::{BettingAgent, Martingale};
+/// use twentyone::counting::HiLo;
+/// use twentyone::game::DEFAULT_CONFIG;
+/// use twentyone::sim::simulate_with_bankroll;
+/// use twentyone::strategy::BasicStrategyAgent;
+///
+/// let agent = BasicStrategyAgent::new(&DEFAULT_CONFIG, 10);
+/// let agent = BettingAgent::new(&DEFAULT_CONFIG, agent, Martingale::new(10), HiLo);
+/// let stats = simulate_with_bankroll(&DEFAULT_CONFIG, agent, 1000, 21, 500);
+/// println!("Played {} rounds, ruined: {}", stats.rounds, stats.ruins > 0);
+/// ```
+pub fn simulate_with_bankroll<A: PlayerAgent + 'static>(
+    config: &GameConfig,
+    agent: A,
+    rounds: u64,
+    seed: u64,
+    bankroll: i32,
+) -> Statistics {
+    run(config, agent, rounds, seed, Some(bankroll))
+}
+
+/// Play rounds with a single player, topping up their money before every round
+/// unless they were given a bankroll
+fn run<A: PlayerAgent + 'static>(
+    config: &GameConfig,
+    agent: A,
+    rounds: u64,
+    seed: u64,
+    bankroll: Option<i32>,
+) -> Statistics {
+    let mut dealer = Dealer::new(Shoe::new(config.shoe_deck_count), *config, silent);
+    dealer.set_seed(seed);
+    dealer.shuffle_shoe();
+    dealer
+        .players_mut()
+        .push(Player::with_agent(bankroll.unwrap_or(BANKROLL), agent));
+
+    // The player is ruined once they can't afford another round
+    let ruined =
+        |dealer: &Dealer| bankroll.is_some() && *dealer.players()[0].money() < config.min_bet;
+
+    let mut stats = Statistics::new();
+    for _ in 0..rounds {
+        if ruined(&dealer) {
+            break;
+        }
+        if bankroll.is_none() {
+            *dealer.players_mut()[0].money_mut() = BANKROLL;
+        }
+        let result = dealer.play_round(true);
+        stats.record(&result.players[0]);
+    }
+    if ruined(&dealer) {
+        stats.ruins += 1;
+    }
+    stats
+}
+
+/// Play rounds split across worker threads and return the merged statistics
+///
+/// Each worker has its own dealer, shoe and agent, seeded from a stream derived
+/// from the master seed. Statistics are merged in the order of the workers,
+/// so the same agent, rules, seed and thread count always give the same statistics.
+/// Changing the thread count changes how the shoes are shuffled,
+/// so the statistics will be different.
+///
+/// # Arguments
+///
+/// * `config` - The rules of the game
+/// * `agent` - Creates the agent for each worker, given the index of the worker
+/// * `rounds` - The total amount of rounds to deal, including any the player sits out
+/// * `seed` - The master seed
+/// * `threads` - The amount of worker threads to use (at least one is always used)
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::game::DEFAULT_CONFIG;
+/// use twentyone::sim::simulate_parallel;
+/// use twentyone::strategy::BasicStrategyAgent;
+///
+/// let agent = |_| BasicStrategyAgent::new(&DEFAULT_CONFIG, 10);
+/// let stats = simulate_parallel(&DEFAULT_CONFIG, agent, 1000, 21, 4);
+/// assert_eq!(stats.rounds, 1000);
+/// assert_eq!(stats, simulate_parallel(&DEFAULT_CONFIG, agent, 1000, 21, 4));
+/// ```
+pub fn simulate_parallel<A, F>(
+    config: &GameConfig,
+    agent: F,
+    rounds: u64,
+    seed: u64,
+    threads: usize,
+) -> Statistics
+where
+    A: PlayerAgent + 'static,
+    F: Fn(usize) -> A + Sync,
+{
+    let threads = threads.max(1);
+    let seeds = worker_seeds(seed, threads);
+    let agent = &agent;
+
+    let results: Vec<Statistics> = thread::scope(|scope| {
+        let workers: Vec<_> = seeds
+            .into_iter()
+            .enumerate()
+            .map(|(i, seed)| {
+                // Spread the remainder over the first workers
+                let share = rounds / threads as u64 + ((i as u64) < rounds % threads as u64) as u64;
+                scope.spawn(move || simulate(config, agent(i), share, seed))
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("simulation worker panicked"))
+            .collect()
+    });
+
+    let mut stats = Statistics::new();
+    for result in results.iter() {
+        stats.merge(result);
+    }
+    stats
+}
+
+/// Returns the seed of each worker, derived from the master seed
+fn worker_seeds(seed: u64, threads: usize) -> Vec<u64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..threads).map(|_| rng.next_u64()).collect()
+}
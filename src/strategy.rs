@@ -0,0 +1,516 @@
+//! Basic strategy generated from the rules of the game
+//!
+//! The strategy is built from the rules that change it the most:
+//! the amount of decks, whether the dealer hits soft 17, doubling after splitting,
+//! surrendering and whether the dealer peeks for blackjack.
+use crate::cards::Card;
+use crate::game::{
+    Dealer, GameConfig, HandValue, HoleCardRule, Player, PlayerAction, PlayerAgent, SurrenderRule,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// What basic strategy says to do with a hand
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Hit,
+    Stand,
+    /// Double down if allowed, otherwise hit
+    DoubleOrHit,
+    /// Double down if allowed, otherwise stand
+    DoubleOrStand,
+    Split,
+    /// Surrender if allowed, otherwise hit
+    SurrenderOrHit,
+    /// Surrender if allowed, otherwise stand
+    SurrenderOrStand,
+    /// Surrender if allowed, otherwise split
+    SurrenderOrSplit,
+}
+
+/// The error returned when an action isn't one of the chart abbreviations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseActionError;
+
+/// The actions that can be taken on a hand, besides hitting and standing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Available {
+    /// Whether the hand can be doubled down
+    pub double: bool,
+    /// Whether the hand can be split
+    pub split: bool,
+    /// Whether the hand can be surrendered
+    pub surrender: bool,
+}
+
+/// A basic strategy table for hard, soft and pair hands against each dealer up card
+///
+/// Dealer up cards are given by their value, from `2` to `11` (an ace).
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::game::{GameConfig, DEFAULT_CONFIG};
+/// use twentyone::strategy::{Action, BasicStrategy};
+///
+/// let s17 = BasicStrategy::new(&DEFAULT_CONFIG);
+/// let h17 = BasicStrategy::new(&GameConfig {
+///     stand_soft_17: false,
+///     ..DEFAULT_CONFIG
+/// });
+///
+/// // Hard 16 against a ten
+/// assert_eq!(s17.hard(16, 10), Action::Hit);
+/// // Hard 11 against an ace
+/// assert_eq!(s17.hard(11, 11), Action::Hit);
+/// assert_eq!(h17.hard(11, 11), Action::DoubleOrHit);
+/// // Soft 18 against a two
+/// assert_eq!(s17.soft(18, 2), Action::Stand);
+/// assert_eq!(h17.soft(18, 2), Action::DoubleOrStand);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicStrategy {
+    /// Hard totals from 4 to 21
+    hard: [[Action; 10]; 18],
+    /// Soft totals from 12 to 21
+    soft: [[Action; 10]; 10],
+    /// Pairs of cards valued from 2 to 11
+    pairs: [[Action; 10]; 10],
+}
+
+/// The rules that affect basic strategy
+struct Rules {
+    decks: u8,
+    hit_soft_17: bool,
+    double_after_split: bool,
+    surrender: SurrenderRule,
+    /// Whether all bets are lost to a dealer blackjack that wasn't peeked for
+    no_peek: bool,
+}
+
+impl BasicStrategy {
+    /// Returns the basic strategy for a game's rules
+    ///
+    /// The amount of decks is taken from `shoe_deck_count`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rules of the game
+    pub fn new(config: &GameConfig) -> BasicStrategy {
+        let rules = Rules {
+            decks: config.shoe_deck_count,
+            hit_soft_17: !config.stand_soft_17,
+            double_after_split: config.doubling_down && config.double_after_split,
+            surrender: config.surrender,
+            no_peek: config.hole_card
+                == (HoleCardRule::NoHoleCard {
+                    original_bets_only: false,
+                }),
+        };
+
+        let mut strategy = BasicStrategy {
+            hard: [[Action::Hit; 10]; 18],
+            soft: [[Action::Hit; 10]; 10],
+            pairs: [[Action::Hit; 10]; 10],
+        };
+        for up in 2..=11 {
+            let column = (up - 2) as usize;
+            for total in 4..=21 {
+                strategy.hard[(total - 4) as usize][column] = hard_action(total, up, &rules);
+            }
+            for total in 12..=21 {
+                strategy.soft[(total - 12) as usize][column] = soft_action(total, up, &rules);
+            }
+            for card in 2..=11 {
+                strategy.pairs[(card - 2) as usize][column] = if split(card, up, &rules) {
+                    // Surrender 8s against an ace rather than splitting them
+                    if card == 8 && up == 11 && rules.surrender != SurrenderRule::None {
+                        if rules.hit_soft_17 || rules.surrender == SurrenderRule::Early {
+                            Action::SurrenderOrSplit
+                        } else {
+                            Action::Split
+                        }
+                    } else if card == 8 && up == 10 && rules.surrender == SurrenderRule::Early {
+                        Action::SurrenderOrSplit
+                    } else {
+                        Action::Split
+                    }
+                } else if card == 11 {
+                    soft_action(12, up, &rules)
+                } else {
+                    hard_action(card * 2, up, &rules)
+                };
+            }
+        }
+        strategy
+    }
+
+    /// Returns the action for a hard total against a dealer up card
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - The hand's total, from 4 to 21
+    /// * `up_card` - The value of the dealer's up card, from 2 to 11
+    pub fn hard(&self, total: u8, up_card: u8) -> Action {
+        self.hard[(total.clamp(4, 21) - 4) as usize][column(up_card)]
+    }
+
+    /// Returns the action for a soft total against a dealer up card
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - The hand's total, from 12 to 21
+    /// * `up_card` - The value of the dealer's up card, from 2 to 11
+    pub fn soft(&self, total: u8, up_card: u8) -> Action {
+        self.soft[(total.clamp(12, 21) - 12) as usize][column(up_card)]
+    }
+
+    /// Returns the action for a pair against a dealer up card
+    ///
+    /// Pairs that shouldn't be split are played as their hard or soft total.
+    ///
+    /// # Arguments
+    ///
+    /// * `card` - The value of each card in the pair, from 2 to 11
+    /// * `up_card` - The value of the dealer's up card, from 2 to 11
+    pub fn pair(&self, card: u8, up_card: u8) -> Action {
+        self.pairs[(card.clamp(2, 11) - 2) as usize][column(up_card)]
+    }
+
+    /// Returns the action to take with a hand against a dealer up card
+    ///
+    /// Actions that aren't available fall back to hitting, standing or splitting
+    /// as the table says.
+    ///
+    /// # Arguments
+    ///
+    /// * `hand` - The hand to play
+    /// * `up_card` - The dealer's up card
+    /// * `available` - The actions that can be taken on the hand
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use twentyone::cards::parse_cards;
+    /// use twentyone::game::{PlayerAction, DEFAULT_CONFIG};
+    /// use twentyone::strategy::{Available, BasicStrategy};
+    ///
+    /// let strategy = BasicStrategy::new(&DEFAULT_CONFIG);
+    /// let up_card = "6D".parse().unwrap();
+    /// let mut available = Available {
+    ///     double: true,
+    ///     split: true,
+    ///     surrender: false,
+    /// };
+    /// let hand = parse_cards("AS 7H").unwrap();
+    /// assert_eq!(strategy.action(&hand, up_card, available), PlayerAction::DoubleDown);
+    /// available.double = false;
+    /// assert_eq!(strategy.action(&hand, up_card, available), PlayerAction::Stand);
+    /// ```
+    pub fn action(&self, hand: &[Card], up_card: Card, available: Available) -> PlayerAction {
+        let value = HandValue::new(hand);
+        let up = up_card.value();
+
+        if value.is_pair && available.split {
+            match self.pair(hand[0].value(), up) {
+                Action::Split => return PlayerAction::Split,
+                Action::SurrenderOrSplit if available.surrender => return PlayerAction::Surrender,
+                Action::SurrenderOrSplit => return PlayerAction::Split,
+                _ => (),
+            }
+        }
+
+        let action = if value.soft {
+            self.soft(value.total, up)
+        } else {
+            self.hard(value.total, up)
+        };
+        action.resolve(available)
+    }
+}
+
+impl Action {
+    /// Returns the action to take on a hand, given the actions that can be taken on it
+    ///
+    /// Splits that aren't available are hit.
+    ///
+    /// # Arguments
+    ///
+    /// * `available` - The actions that can be taken on the hand
+    pub fn resolve(self, available: Available) -> PlayerAction {
+        match self {
+            Action::Stand => PlayerAction::Stand,
+            Action::DoubleOrHit | Action::DoubleOrStand if available.double => {
+                PlayerAction::DoubleDown
+            }
+            Action::DoubleOrStand => PlayerAction::Stand,
+            Action::SurrenderOrHit | Action::SurrenderOrStand | Action::SurrenderOrSplit
+                if available.surrender =>
+            {
+                PlayerAction::Surrender
+            }
+            Action::SurrenderOrStand => PlayerAction::Stand,
+            Action::Split | Action::SurrenderOrSplit if available.split => PlayerAction::Split,
+            _ => PlayerAction::Hit,
+        }
+    }
+}
+
+/// Returns the column of a table for a dealer up card
+fn column(up_card: u8) -> usize {
+    (up_card.clamp(2, 11) - 2) as usize
+}
+
+/// Returns the action for a hard total
+fn hard_action(total: u8, up: u8, rules: &Rules) -> Action {
+    // Don't risk more money against a ten or ace that might already be a blackjack
+    let double_against = !(rules.no_peek && up >= 10);
+    let action = match total {
+        8 if rules.decks == 1 && (up == 5 || up == 6) => Action::DoubleOrHit,
+        9 if (3..=6).contains(&up) || (up == 2 && rules.decks <= 2) => Action::DoubleOrHit,
+        10 if up <= 9 => Action::DoubleOrHit,
+        11 if up <= 10 || rules.hit_soft_17 || rules.decks <= 2 => {
+            if double_against {
+                Action::DoubleOrHit
+            } else {
+                Action::Hit
+            }
+        }
+        12 if (4..=6).contains(&up) => Action::Stand,
+        13..=16 if up <= 6 => Action::Stand,
+        17..=21 => Action::Stand,
+        _ => Action::Hit,
+    };
+
+    let surrender = match rules.surrender {
+        SurrenderRule::None => false,
+        // Surrender more hands if the bet is lost anyway when the dealer has blackjack
+        SurrenderRule::Early => match up {
+            11 => (5..=7).contains(&total) || (12..=17).contains(&total),
+            10 => (14..=16).contains(&total),
+            9 => total == 16,
+            _ => false,
+        },
+        SurrenderRule::Late => match up {
+            11 => total == 16 || (rules.hit_soft_17 && (total == 15 || total == 17)),
+            10 => total == 15 || total == 16,
+            9 => total == 16,
+            _ => false,
+        },
+    };
+    match (surrender, action) {
+        (true, Action::Stand) => Action::SurrenderOrStand,
+        (true, _) => Action::SurrenderOrHit,
+        _ => action,
+    }
+}
+
+/// Returns the action for a soft total
+fn soft_action(total: u8, up: u8, rules: &Rules) -> Action {
+    match total {
+        13 | 14 if up == 5 || up == 6 || (up == 4 && rules.decks == 1) => Action::DoubleOrHit,
+        15 | 16 if (4..=6).contains(&up) => Action::DoubleOrHit,
+        17 if (3..=6).contains(&up) => Action::DoubleOrHit,
+        18 if (3..=6).contains(&up) || (up == 2 && rules.hit_soft_17) => Action::DoubleOrStand,
+        18 if up >= 9 => Action::Hit,
+        19 if up == 6 && rules.hit_soft_17 => Action::DoubleOrStand,
+        18..=21 => Action::Stand,
+        _ => Action::Hit,
+    }
+}
+
+/// Returns whether a pair should be split
+fn split(card: u8, up: u8, rules: &Rules) -> bool {
+    // Don't risk more money against a ten or ace that might already be a blackjack
+    if rules.no_peek && up >= 10 && (card == 8 || card == 11) {
+        return false;
+    }
+    let das = rules.double_after_split;
+    match card {
+        11 | 8 => true,
+        9 => up <= 9 && up != 7,
+        7 => up <= 7,
+        6 => up <= 6 && (das || up >= 3),
+        4 => das && (up == 5 || up == 6),
+        2 | 3 => up <= 7 && (das || up >= 4),
+        _ => false,
+    }
+}
+
+/// Formats an action as it is written in strategy charts (eg. `Ds` for double or stand)
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Action::Hit => "H",
+            Action::Stand => "S",
+            Action::DoubleOrHit => "D",
+            Action::DoubleOrStand => "Ds",
+            Action::Split => "P",
+            Action::SurrenderOrHit => "Rh",
+            Action::SurrenderOrStand => "Rs",
+            Action::SurrenderOrSplit => "Rp",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Parses an action as it is written in strategy charts (eg. `Ds` for double or stand)
+impl FromStr for Action {
+    type Err = ParseActionError;
+
+    fn from_str(s: &str) -> Result<Action, ParseActionError> {
+        match s.trim() {
+            "H" => Ok(Action::Hit),
+            "S" => Ok(Action::Stand),
+            "D" => Ok(Action::DoubleOrHit),
+            "Ds" => Ok(Action::DoubleOrStand),
+            "P" => Ok(Action::Split),
+            "Rh" => Ok(Action::SurrenderOrHit),
+            "Rs" => Ok(Action::SurrenderOrStand),
+            "Rp" => Ok(Action::SurrenderOrSplit),
+            _ => Err(ParseActionError),
+        }
+    }
+}
+
+impl fmt::Display for ParseActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an action must be one of H, S, D, Ds, P, Rh, Rs or Rp")
+    }
+}
+
+impl std::error::Error for ParseActionError {}
+
+/// Formats the strategy as hard, soft and pair charts
+impl fmt::Display for BasicStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let header = |f: &mut fmt::Formatter, name: &str| {
+            write!(f, "{:<6}", name)?;
+            for up in 2..=11 {
+                match up {
+                    11 => write!(f, "{:>3}", "A")?,
+                    _ => write!(f, "{:>3}", up)?,
+                }
+            }
+            writeln!(f)
+        };
+        let row = |f: &mut fmt::Formatter, name: String, actions: &[Action; 10]| {
+            write!(f, "{:<6}", name)?;
+            for action in actions.iter() {
+                write!(f, "{:>3}", action.to_string())?;
+            }
+            writeln!(f)
+        };
+
+        header(f, "Hard")?;
+        for (i, actions) in self.hard.iter().enumerate() {
+            row(f, (i + 4).to_string(), actions)?;
+        }
+        header(f, "Soft")?;
+        for (i, actions) in self.soft.iter().enumerate() {
+            let name = match i {
+                0 => "A,A".to_string(),
+                _ => format!("A,{}", i + 1),
+            };
+            row(f, name, actions)?;
+        }
+        header(f, "Pairs")?;
+        for (i, actions) in self.pairs.iter().enumerate() {
+            let card = match i + 2 {
+                11 => "A".to_string(),
+                card => card.to_string(),
+            };
+            row(f, format!("{},{}", card, card), actions)?;
+        }
+        Ok(())
+    }
+}
+
+/// An agent that plays basic strategy with a flat bet
+///
+/// Insurance and even money are always declined.
+/// The player sits out any round they can't afford the bet for.
+///
+/// # Examples
+///
+/// ```
+/// use twentyone::prelude::*;
+/// use twentyone::strategy::BasicStrategyAgent;
+/// # fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+/// #     PlayerAction::None
+/// # }
+///
+/// let mut shoe = create_shoe(6);
+/// shuffle_deck(&mut shoe);
+/// let mut dealer = Dealer::new(shoe, DEFAULT_CONFIG, &callback);
+/// let agent = BasicStrategyAgent::new(&DEFAULT_CONFIG, 10);
+/// dealer.players_mut().push(Player::with_agent(1000, agent));
+/// dealer.play_round(true);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicStrategyAgent {
+    strategy: BasicStrategy,
+    bet: i32,
+}
+
+impl BasicStrategyAgent {
+    /// Returns a new BasicStrategyAgent
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rules of the game to build the strategy for
+    /// * `bet` - The amount to bet every round
+    pub fn new(config: &GameConfig, bet: i32) -> BasicStrategyAgent {
+        BasicStrategyAgent::with_strategy(BasicStrategy::new(config), bet)
+    }
+
+    /// Returns a new BasicStrategyAgent that plays an existing strategy
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - The strategy to play
+    /// * `bet` - The amount to bet every round
+    pub fn with_strategy(strategy: BasicStrategy, bet: i32) -> BasicStrategyAgent {
+        BasicStrategyAgent { strategy, bet }
+    }
+
+    /// Returns a reference to the strategy the agent plays
+    pub fn strategy(&self) -> &BasicStrategy {
+        &self.strategy
+    }
+}
+
+impl PlayerAgent for BasicStrategyAgent {
+    fn bet(&mut self, player: &Player, _dealer: &Dealer) -> PlayerAction {
+        // Sit out rather than make a bet the dealer would refuse
+        if *player.money() < self.bet {
+            return PlayerAction::None;
+        }
+        PlayerAction::Bet(self.bet)
+    }
+
+    fn play(&mut self, hand: usize, player: &Player, dealer: &Dealer) -> PlayerAction {
+        let available = Available {
+            double: dealer.can_double(),
+            split: dealer.can_split(),
+            surrender: dealer.can_surrender(),
+        };
+        self.strategy
+            .action(&player.hands()[hand], dealer.up_card().unwrap(), available)
+    }
+
+    fn surrender(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+        let available = Available {
+            double: true,
+            split: true,
+            surrender: true,
+        };
+        match self
+            .strategy
+            .action(&player.hands()[0], dealer.up_card().unwrap(), available)
+        {
+            PlayerAction::Surrender => PlayerAction::Surrender,
+            _ => PlayerAction::None,
+        }
+    }
+}
@@ -1,36 +1,86 @@
 #[cfg(test)]
 mod tests {
-    use twentyone::game::{Dealer, DealerRequest, Player, PlayerAction};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::cell::Cell;
+    use std::convert::TryFrom;
+    use std::rc::Rc;
+    use twentyone::analysis::{Analyzer, Composition};
+    use twentyone::betting::{
+        BetInfo, BettingAgent, BettingPolicy, Flat, Kelly, Martingale, OneThreeTwoSix, Paroli,
+        TrueCountRamp, Wonging,
+    };
+    use twentyone::cards::{Card, Rank, Suit};
+    use twentyone::counting::{Counter, CountingSystem, HiLo, KnockOut, OmegaII, WongHalves, Zen};
+    use twentyone::deviations::{
+        Deviation, Deviations, IndexPlayAgent, ParseDeviationError, Situation,
+    };
+    use twentyone::game::{
+        Dealer, DealerRequest, Decision, HandResult, HoleCardRule, Outcome, Player, PlayerAction,
+        PlayerActionError, PlayerAgent, SideBet, SideBetKind, SideBetOutcome, SideBetResult,
+        SurrenderRule,
+    };
+    use twentyone::risk::{self, RiskAnalysis};
+    use twentyone::shoe::{self, Shoe, ShuffleMode};
+    use twentyone::shuffle::{self, ShuffleProcedure, ShuffleStep};
+    use twentyone::side_bets::{Buster, LuckyLadies, PerfectPairs, TwentyOnePlusThree};
+    use twentyone::sim::{self, Statistics};
+    use twentyone::strategy::{Action, Available, BasicStrategy, BasicStrategyAgent};
     use twentyone::{cards, game};
 
     #[test]
     fn deck_tests() {
         let mut deck = cards::create_deck();
         cards::shuffle_deck(&mut deck);
-        let card = deck.get(0).unwrap().clone();
+        let card = deck[0];
         // Draw card (returns first card of deck and removes it from the vector)
         assert_eq!(cards::draw_card(&mut deck).unwrap(), card);
         // Ensure that the vector length has been reduced from 52 to 51
         assert_eq!(deck.len(), 51);
+        // Drawing from an empty deck is an error
+        assert_eq!(cards::draw_card(&mut Vec::new()), Err(()));
     }
 
     #[test]
     fn shoe_tests() {
         let mut shoe = cards::create_shoe(6);
         cards::shuffle_deck(&mut shoe);
-        let card = shoe.get(0).unwrap().clone();
+        let card = shoe[0];
         // Draw card (returns first card of deck and removes it from the vector)
         assert_eq!(cards::draw_card(&mut shoe).unwrap(), card);
         // Ensure that the vector length has been reduced from 312 to 311
         assert_eq!(shoe.len(), 311);
     }
 
+    #[test]
+    fn card_tests() {
+        let card = Card::new(Rank::Ten, Suit::Hearts);
+        // Display and parse round-trip
+        assert_eq!(card.to_string(), "TH");
+        assert_eq!("TH".parse::<Card>(), Ok(card));
+        assert_eq!("10h".parse::<Card>(), Ok(card));
+        assert!("XH".parse::<Card>().is_err());
+        assert!("THH".parse::<Card>().is_err());
+        // Conversion to and from the two-character form
+        assert_eq!(<[char; 2]>::from(card), ['H', 'T']);
+        assert_eq!(Card::try_from(['H', 'T']), Ok(card));
+        assert!(Card::try_from(['X', '1']).is_err());
+        // Ordering and values
+        assert!(Rank::Ace > Rank::King);
+        assert_eq!(Card::new(Rank::Ace, Suit::Spades).value(), 11);
+        assert_eq!(Card::new(Rank::Queen, Suit::Clubs).value(), 10);
+        let mut deck = cards::create_deck();
+        deck.reverse();
+        deck.sort();
+        assert_eq!(deck, cards::create_deck());
+    }
+
     #[test]
     fn hand_tests() {
         let mut deck = cards::create_deck();
         cards::shuffle_deck(&mut deck);
-        let mut hand: Vec<[char; 2]> = Vec::new();
-        let card = deck.get(0).unwrap().clone();
+        let mut hand: Vec<Card> = Vec::new();
+        let card = deck[0];
         // Hit card from deck to hand
         cards::hit_card(&mut deck, &mut hand);
         assert_eq!(card, hand[0]);
@@ -40,7 +90,7 @@ mod tests {
     fn game_tests() {
         let mut deck = cards::create_deck();
         // Test hand value calculation
-        let deck_slice = &deck[..13].iter().cloned().collect();
+        let deck_slice: Vec<Card> = deck[..13].to_vec();
         assert_eq!(game::get_hand_value(&deck_slice, false), 95);
 
         cards::shuffle_deck(&mut deck);
@@ -48,7 +98,7 @@ mod tests {
         cards::hit_card(&mut deck, &mut hand);
         cards::hit_card(&mut deck, &mut hand);
         // Test hand splitting checks
-        assert_eq!(game::can_split(&hand), hand[0][1] == hand[1][1]);
+        assert_eq!(game::can_split(&hand), hand[0].rank == hand[1].rank);
     }
 
     #[test]
@@ -71,11 +121,11 @@ mod tests {
                     PlayerAction::Bet(10)
                 }
                 DealerRequest::UpCard(card) => {
-                    println!("Dealer up card: {}{}", card[0], card[1]);
+                    println!("Dealer up card: {}", card);
                     PlayerAction::None
                 }
                 DealerRequest::HitCard(card) => {
-                    println!("Dealer hit card: {}{}", card[0], card[1]);
+                    println!("Dealer hit card: {}", card);
                     PlayerAction::None
                 }
                 DealerRequest::DealerHand(hand) => {
@@ -93,6 +143,18 @@ mod tests {
                     println!("Dealer low on cards, automatically creating new shoe");
                     PlayerAction::None
                 }
+                DealerRequest::Surrender => {
+                    println!("Dealer offered early surrender, declining");
+                    PlayerAction::None
+                }
+                DealerRequest::Insurance => {
+                    println!("Dealer offered insurance, declining");
+                    PlayerAction::None
+                }
+                DealerRequest::SideBet(_) => {
+                    println!("Dealer offered a side bet, declining");
+                    PlayerAction::None
+                }
             }
         }
 
@@ -111,4 +173,2058 @@ mod tests {
             dealer.play_round(true);
         }
     }
+
+    #[test]
+    fn seeded_tests() {
+        fn callback(request: DealerRequest, player: Option<&Player>, _: &Dealer) -> PlayerAction {
+            match request {
+                DealerRequest::Play(i) => {
+                    if game::get_hand_value(&player.unwrap().hands()[i], true) < 17 {
+                        PlayerAction::Hit
+                    } else {
+                        PlayerAction::Stand
+                    }
+                }
+                DealerRequest::Bet => PlayerAction::Bet(10),
+                _ => PlayerAction::None,
+            }
+        }
+
+        // The same seed should always produce the same shoe
+        let mut first = cards::create_shoe(1);
+        let mut second = cards::create_shoe(1);
+        cards::shuffle_deck_seeded(&mut first, 21);
+        cards::shuffle_deck_seeded(&mut second, 21);
+        assert_eq!(first, second);
+
+        // The same seed should always produce the same rounds, including reshuffles
+        let play = |shoe: Vec<Card>| {
+            let mut dealer = Dealer::new(shoe, game::DEFAULT_CONFIG, &callback);
+            dealer.set_seed(21);
+            dealer.players_mut().push(Player::new(1000));
+            let mut history = Vec::new();
+            for _ in 0..20 {
+                dealer.play_round(true);
+                history.push((dealer.hand().clone(), *dealer.players()[0].money()));
+            }
+            (history, dealer.shoe().clone())
+        };
+        assert_eq!(play(first), play(second));
+    }
+
+    #[test]
+    fn round_tests() {
+        fn callback(_: DealerRequest, _: Option<&Player>, _: &Dealer) -> PlayerAction {
+            PlayerAction::None
+        }
+
+        // Stack the shoe so the dealer can't have blackjack
+        let mut shoe = stacked("TH 7D 8C 9S");
+        shoe.append(&mut cards::create_shoe(6));
+        let mut dealer = Dealer::new(shoe, game::DEFAULT_CONFIG, &callback);
+        dealer.players_mut().push(Player::new(100));
+
+        // No decision is pending outside of a round
+        assert_eq!(dealer.pending(), None);
+        assert!(dealer.apply(PlayerAction::Stand).is_err());
+
+        assert!(dealer.start_round(true).is_empty());
+        assert_eq!(dealer.pending(), Some(Decision::Bet { player: 0 }));
+        // Invalid actions leave the decision pending
+        assert_eq!(
+            dealer.apply(PlayerAction::Hit),
+            Err(PlayerActionError::UnexpectedAction(0, PlayerAction::Hit))
+        );
+        assert_eq!(
+            dealer.apply(PlayerAction::Bet(1000)),
+            Err(PlayerActionError::NotEnoughMoney(
+                0,
+                PlayerAction::Bet(1000)
+            ))
+        );
+        assert_eq!(dealer.pending(), Some(Decision::Bet { player: 0 }));
+
+        // Betting deals the hands and shows the up card
+        let events = dealer.apply(PlayerAction::Bet(10)).unwrap();
+        assert_eq!(events, vec![DealerRequest::UpCard(dealer.hand()[0])]);
+        assert_eq!(*dealer.players()[0].money(), 90);
+        assert_eq!(dealer.players()[0].hands()[0].len(), 2);
+        assert_eq!(
+            dealer.pending(),
+            Some(Decision::Play { player: 0, hand: 0 })
+        );
+
+        // Standing finishes the round
+        let events = dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(
+            events.last(),
+            Some(&DealerRequest::DealerHand(dealer.hand().clone()))
+        );
+        assert_eq!(dealer.pending(), None);
+    }
+
+    #[test]
+    fn agent_tests() {
+        // Bets one more than last round and counts the up cards it sees
+        struct Agent {
+            bet: i32,
+            up_cards: Rc<Cell<u32>>,
+        }
+
+        impl PlayerAgent for Agent {
+            fn bet(&mut self, _: &Player, _: &Dealer) -> PlayerAction {
+                self.bet += 1;
+                PlayerAction::Bet(self.bet)
+            }
+
+            fn play(&mut self, _: usize, _: &Player, _: &Dealer) -> PlayerAction {
+                PlayerAction::Stand
+            }
+
+            fn up_card(&mut self, _: Card, _: &Dealer) {
+                self.up_cards.set(self.up_cards.get() + 1);
+            }
+        }
+
+        // The shared callback should only be sent updates
+        let callback = |request: DealerRequest, _: Option<&Player>, _: &Dealer| match request {
+            DealerRequest::Bet | DealerRequest::Play(_) => panic!("Seat agent was skipped"),
+            _ => PlayerAction::None,
+        };
+
+        let up_cards = Rc::new(Cell::new(0));
+        let mut shoe = cards::create_shoe(6);
+        cards::shuffle_deck(&mut shoe);
+        let mut dealer = Dealer::new(shoe, game::DEFAULT_CONFIG, callback);
+        for _ in 0..2 {
+            dealer.players_mut().push(Player::with_agent(
+                1000,
+                Agent {
+                    bet: 0,
+                    up_cards: up_cards.clone(),
+                },
+            ));
+        }
+
+        for _ in 0..3 {
+            dealer.play_round(true);
+        }
+        // Each seat kept its own state and bet 1, 2 and 3
+        for player in dealer.players() {
+            assert!(*player.money() >= 1000 - 6);
+        }
+        assert_eq!(up_cards.get(), 6);
+    }
+
+    /// Returns a shoe that deals the given cards in order
+    fn stacked(cards: &str) -> Vec<Card> {
+        cards::parse_cards(cards).unwrap()
+    }
+
+    /// Returns a dealer with one player, in a round waiting for the player's first action
+    fn dealt<'a>(config: game::GameConfig, cards: &str) -> Dealer<'a> {
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let mut dealer = Dealer::new(stacked(cards), config, callback);
+        dealer.players_mut().push(Player::new(100));
+        dealer.start_round(true);
+        dealer.apply(PlayerAction::Bet(10)).unwrap();
+        dealer
+    }
+
+    #[test]
+    fn surrender_tests() {
+        let config = |surrender| game::GameConfig {
+            surrender,
+            low_cards_threshold: 0,
+            ..game::DEFAULT_CONFIG
+        };
+
+        // Not allowed by the config
+        let mut dealer = dealt(config(SurrenderRule::None), "TH 8D 6C 9S 2H");
+        assert_eq!(
+            dealer.apply(PlayerAction::Surrender),
+            Err(PlayerActionError::SurrenderNotAllowed(0))
+        );
+
+        // Not allowed after hitting
+        let mut dealer = dealt(config(SurrenderRule::Late), "TH 8D 2C 9S 2H 5S");
+        dealer.apply(PlayerAction::Hit).unwrap();
+        assert_eq!(
+            dealer.apply(PlayerAction::Surrender),
+            Err(PlayerActionError::SurrenderNotAllowed(0))
+        );
+
+        // Late surrender refunds half unless the dealer has blackjack
+        let mut dealer = dealt(config(SurrenderRule::Late), "TH 8D 6C 9S 2H");
+        dealer.apply(PlayerAction::Surrender).unwrap();
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 95);
+
+        // The dealer peeks before late surrender is possible
+        let dealer = dealt(config(SurrenderRule::Late), "TH KD 6C AS");
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 90);
+
+        // Early surrender is offered before the dealer peeks,
+        // and refunds half even if the dealer has blackjack
+        let mut dealer = dealt(config(SurrenderRule::Early), "TH KD 6C AS");
+        assert_eq!(dealer.pending(), Some(Decision::Surrender { player: 0 }));
+        dealer.apply(PlayerAction::Surrender).unwrap();
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 95);
+
+        // Late surrender loses everything to a no-hole-card blackjack
+        let no_hole_card = |surrender| game::GameConfig {
+            hole_card: HoleCardRule::NoHoleCard {
+                original_bets_only: true,
+            },
+            ..config(surrender)
+        };
+        let mut dealer = dealt(no_hole_card(SurrenderRule::Late), "TH KD 6C AS");
+        dealer.apply(PlayerAction::Surrender).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 90);
+        let mut dealer = dealt(no_hole_card(SurrenderRule::Early), "TH KD 6C AS");
+        dealer.apply(PlayerAction::Surrender).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 95);
+    }
+
+    #[test]
+    fn insurance_tests() {
+        let config = game::GameConfig {
+            low_cards_threshold: 0,
+            ..game::DEFAULT_CONFIG
+        };
+
+        // Insurance is offered when the dealer shows an ace, and pays 2 to 1
+        let mut dealer = dealt(config, "9H AD 9C KS");
+        assert_eq!(dealer.pending(), Some(Decision::Insurance { player: 0 }));
+        // Insurance is limited to half of the bet
+        assert!(dealer.apply(PlayerAction::Insurance(6)).is_err());
+        dealer.apply(PlayerAction::Insurance(5)).unwrap();
+        // The dealer peeks and has blackjack, so the round is over
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 100);
+
+        // Insurance is lost when the dealer does not have blackjack
+        let mut dealer = dealt(config, "9H AD 9C 8S");
+        dealer.apply(PlayerAction::Insurance(5)).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 85);
+
+        // Declining insurance
+        let mut dealer = dealt(config, "9H AD 9C 7S");
+        dealer.apply(PlayerAction::None).unwrap();
+        assert_eq!(
+            dealer.pending(),
+            Some(Decision::Play { player: 0, hand: 0 })
+        );
+
+        // Players with blackjack take even money
+        let mut dealer = dealt(config, "AH AD KC KS");
+        dealer.apply(PlayerAction::Insurance(5)).unwrap();
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 110);
+
+        // Even money isn't limited to half of the bet
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let mut dealer = Dealer::new(stacked("AH AD KC KS"), config, callback);
+        dealer.players_mut().push(Player::new(100));
+        dealer.start_round(true);
+        dealer.apply(PlayerAction::Bet(1)).unwrap();
+        assert_eq!(dealer.pending(), Some(Decision::Insurance { player: 0 }));
+        dealer.apply(PlayerAction::Insurance(1)).unwrap();
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 101);
+        let result = &dealer.result().unwrap().players[0];
+        assert_eq!(result.hands[0].outcome, Outcome::Win);
+
+        // Odd bets can be insured for half of the bet, rounded up
+        let mut dealer = Dealer::new(stacked("9H AD 9C 8S"), config, callback);
+        dealer.players_mut().push(Player::new(100));
+        dealer.start_round(true);
+        dealer.apply(PlayerAction::Bet(1)).unwrap();
+        assert!(dealer.apply(PlayerAction::Insurance(2)).is_err());
+        dealer.apply(PlayerAction::Insurance(1)).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 98);
+
+        // Insurance can be turned off
+        let dealer = dealt(
+            game::GameConfig {
+                insurance: false,
+                ..config
+            },
+            "9H AD 9C 7S",
+        );
+        assert_eq!(
+            dealer.pending(),
+            Some(Decision::Play { player: 0, hand: 0 })
+        );
+    }
+
+    #[test]
+    fn hole_card_tests() {
+        let config = game::GameConfig {
+            low_cards_threshold: 0,
+            ..game::DEFAULT_CONFIG
+        };
+
+        // The dealer peeks with a ten-value up card and ends the round
+        let dealer = dealt(config, "TH KD QC AS");
+        assert_eq!(dealer.up_card(), Some("KD".parse().unwrap()));
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 90);
+
+        // Player blackjacks push against a dealer blackjack
+        let dealer = dealt(config, "AH KD QC AS");
+        assert_eq!(*dealer.players()[0].money(), 100);
+
+        // The dealer doesn't peek with any other up card
+        let dealer = dealt(config, "TH 9D QC AS");
+        assert_eq!(
+            dealer.pending(),
+            Some(Decision::Play { player: 0, hand: 0 })
+        );
+
+        // No-hole-card dealers take their second card after players finish
+        let no_hole_card = |original_bets_only| game::GameConfig {
+            hole_card: HoleCardRule::NoHoleCard { original_bets_only },
+            insurance: false,
+            ..config
+        };
+        let mut dealer = dealt(no_hole_card(true), "6H AD 5C 9S KS");
+        assert_eq!(dealer.hand().len(), 1);
+        dealer.apply(PlayerAction::DoubleDown).unwrap();
+        assert_eq!(dealer.hand().len(), 2);
+        // Only the original bet is lost
+        assert_eq!(*dealer.players()[0].money(), 90);
+
+        let mut dealer = dealt(no_hole_card(false), "6H AD 5C 9S KS");
+        dealer.apply(PlayerAction::DoubleDown).unwrap();
+        // Everything is lost
+        assert_eq!(*dealer.players()[0].money(), 80);
+    }
+
+    #[test]
+    fn split_tests() {
+        let config = game::GameConfig {
+            low_cards_threshold: 0,
+            ..game::DEFAULT_CONFIG
+        };
+        let play = |hand| Some(Decision::Play { player: 0, hand });
+
+        // Resplit up to four hands
+        let mut dealer = dealt(config, "8H 6D 8C TS 8S 8D 3C 2H 4H 8S 9C");
+        dealer.apply(PlayerAction::Split).unwrap();
+        assert_eq!(dealer.pending(), play(0));
+        dealer.apply(PlayerAction::Split).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        // Split a hand other than the first
+        assert_eq!(dealer.pending(), play(2));
+        dealer.apply(PlayerAction::Split).unwrap();
+        let hands: Vec<_> = dealer.players()[0]
+            .hands()
+            .iter()
+            .map(|hand| hand.cards().clone())
+            .collect();
+        assert_eq!(
+            hands,
+            vec![
+                stacked("8H 3C"),
+                stacked("8S 2H"),
+                stacked("8C 4H"),
+                stacked("8D 8S")
+            ]
+        );
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(
+            dealer.apply(PlayerAction::Split),
+            Err(PlayerActionError::UnexpectedAction(3, PlayerAction::Split))
+        );
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 140);
+
+        // Split aces receive one card each and can't be resplit
+        let mut dealer = dealt(config, "AH 6D AC TS 5C AD 9C");
+        dealer.apply(PlayerAction::Split).unwrap();
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 120);
+
+        // Resplitting aces
+        let resplit_aces = game::GameConfig {
+            resplit_aces: true,
+            ..config
+        };
+        let mut dealer = dealt(resplit_aces, "AH 6D AC TS 5C AD 7C 8C 9C");
+        dealer.apply(PlayerAction::Split).unwrap();
+        assert_eq!(dealer.pending(), play(1));
+        assert_eq!(
+            dealer.apply(PlayerAction::Hit),
+            Err(PlayerActionError::UnexpectedAction(1, PlayerAction::Hit))
+        );
+        dealer.apply(PlayerAction::Split).unwrap();
+        assert_eq!(dealer.pending(), None);
+        assert_eq!(*dealer.players()[0].money(), 130);
+
+        // Hitting split aces
+        let hit_split_aces = game::GameConfig {
+            hit_split_aces: true,
+            ..config
+        };
+        let mut dealer = dealt(hit_split_aces, "AH 6D AC TS 5C 9D 2C 9C");
+        dealer.apply(PlayerAction::Split).unwrap();
+        dealer.apply(PlayerAction::Hit).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(dealer.players()[0].hands()[0].cards(), &stacked("AH 5C 2C"));
+        assert_eq!(*dealer.players()[0].money(), 120);
+
+        // A ten on a split ace is a regular 21 unless allowed
+        let mut dealer = dealt(config, "AH 6D AC TS KC QD 2C");
+        dealer.apply(PlayerAction::Split).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 120);
+
+        let split_ace_blackjack = game::GameConfig {
+            split_ace_blackjack: true,
+            ..config
+        };
+        let mut dealer = dealt(split_ace_blackjack, "AH 6D AC TS KC QD 2C");
+        dealer.apply(PlayerAction::Split).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 130);
+    }
+
+    #[test]
+    fn hand_bet_tests() {
+        let config = game::GameConfig {
+            low_cards_threshold: 0,
+            ..game::DEFAULT_CONFIG
+        };
+
+        // Doubling one split hand only doubles that hand's wager
+        let mut dealer = dealt(config, "8H 6D 8C TS 3C 9D TC 2H");
+        dealer.apply(PlayerAction::Split).unwrap();
+        dealer.apply(PlayerAction::DoubleDown).unwrap();
+        let hands = dealer.players()[0].hands();
+        assert_eq!((*hands[0].wager(), hands[0].doubled()), (20, true));
+        assert_eq!((*hands[1].wager(), hands[1].doubled()), (10, false));
+        assert_eq!(hands[1].split_from(), Some(0));
+        assert_eq!(*dealer.players()[0].money(), 70);
+        dealer.apply(PlayerAction::Stand).unwrap();
+        // The doubled 21 wins against 18 and the 17 loses
+        assert_eq!(*dealer.players()[0].money(), 110);
+
+        // Doubling the second split hand, with the dealer busting
+        let mut dealer = dealt(config, "8H 6D 8C TS TC 3C 9D TH");
+        dealer.apply(PlayerAction::Split).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        dealer.apply(PlayerAction::DoubleDown).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 130);
+
+        // Doubling down after splitting can be turned off
+        let no_das = game::GameConfig {
+            double_after_split: false,
+            ..config
+        };
+        let mut dealer = dealt(no_das, "8H 6D 8C TS 3C 9D TC 2H");
+        dealer.apply(PlayerAction::Split).unwrap();
+        assert_eq!(
+            dealer.apply(PlayerAction::DoubleDown),
+            Err(PlayerActionError::UnexpectedAction(
+                0,
+                PlayerAction::DoubleDown
+            ))
+        );
+
+        // Split and doubled bets against a no-hole-card blackjack
+        let no_hole_card = |original_bets_only| game::GameConfig {
+            hole_card: HoleCardRule::NoHoleCard { original_bets_only },
+            insurance: false,
+            ..config
+        };
+        let mut dealer = dealt(no_hole_card(true), "8H TD 8C 3C 9D 7C AS");
+        dealer.apply(PlayerAction::Split).unwrap();
+        dealer.apply(PlayerAction::DoubleDown).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 90);
+
+        let mut dealer = dealt(no_hole_card(false), "8H TD 8C 3C 9D 7C AS");
+        dealer.apply(PlayerAction::Split).unwrap();
+        dealer.apply(PlayerAction::DoubleDown).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 70);
+    }
+
+    #[test]
+    fn result_tests() {
+        let config = game::GameConfig {
+            surrender: SurrenderRule::Late,
+            low_cards_threshold: 0,
+            ..game::DEFAULT_CONFIG
+        };
+        let hand_result = |outcome, wagered, net| HandResult {
+            outcome,
+            wagered,
+            net,
+        };
+
+        // No result until the round is over
+        let mut dealer = dealt(config, "8H 6D 8C TS 3C 9D TC 2H");
+        assert_eq!(dealer.result(), None);
+        dealer.apply(PlayerAction::Split).unwrap();
+        dealer.apply(PlayerAction::DoubleDown).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        let result = dealer.result().unwrap();
+        assert_eq!(
+            result.players[0].hands,
+            vec![
+                hand_result(Outcome::Win, 20, 20),
+                hand_result(Outcome::Loss, 10, -10)
+            ]
+        );
+        assert_eq!(result.players[0].net(), 10);
+        assert_eq!(result.dealer_hand, stacked("6D TS 2H"));
+        assert!(!result.dealer_busted);
+
+        // Blackjack
+        let mut dealer = dealt(config, "AH 6D KC TS 9C");
+        dealer.apply(PlayerAction::Stand).unwrap();
+        let result = dealer.result().unwrap();
+        assert_eq!(
+            result.players[0].hands,
+            vec![hand_result(Outcome::Blackjack, 10, 15)]
+        );
+        assert!(result.dealer_busted);
+
+        // Bust
+        let mut dealer = dealt(config, "TH 6D 6C TS 9C 8C");
+        dealer.apply(PlayerAction::Hit).unwrap();
+        let result = dealer.result().unwrap();
+        assert_eq!(
+            result.players[0].hands,
+            vec![hand_result(Outcome::Bust, 10, -10)]
+        );
+        // Busted hands lose even if the dealer busts
+        assert!(result.dealer_busted);
+
+        // Push
+        let mut dealer = dealt(config, "TH 7D 7C TS");
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(
+            dealer.result().unwrap().players[0].hands,
+            vec![hand_result(Outcome::Push, 10, 0)]
+        );
+
+        // Surrender
+        let mut dealer = dealt(config, "TH 7D 6C TS");
+        dealer.apply(PlayerAction::Surrender).unwrap();
+        assert_eq!(
+            dealer.result().unwrap().players[0].hands,
+            vec![hand_result(Outcome::Surrender, 10, -5)]
+        );
+
+        // Insurance won against a dealer blackjack
+        let mut dealer = dealt(config, "9H AD 9C KS");
+        dealer.apply(PlayerAction::Insurance(5)).unwrap();
+        let result = dealer.result().unwrap();
+        assert_eq!(result.players[0].insurance, 10);
+        assert_eq!(result.players[0].net(), 0);
+        assert_eq!(*dealer.players()[0].money() - 100, result.players[0].net());
+    }
+
+    #[test]
+    fn shoe_type_tests() {
+        let mut shoe = Shoe::new(1);
+        assert_eq!((shoe.remaining(), shoe.dealt()), (52, 0));
+        // Cards are drawn in order
+        assert_eq!(shoe.draw(), Some(Card::new(Rank::Two, Suit::Spades)));
+
+        // The cut card comes out after the penetration is dealt
+        shoe.set_penetration(0.5);
+        for _ in 0..25 {
+            assert!(!shoe.cut_card_reached());
+            shoe.draw();
+        }
+        assert!(shoe.cut_card_reached());
+
+        // Burned cards go to the discard tray
+        shoe.burn(2);
+        assert_eq!(shoe.discards().len(), 2);
+        assert_eq!((shoe.remaining(), shoe.dealt()), (24, 28));
+
+        // Only discarded cards are shuffled back in
+        shoe.shuffle(&mut StdRng::seed_from_u64(21));
+        assert_eq!((shoe.remaining(), shoe.dealt()), (26, 0));
+        assert!(shoe.discards().is_empty());
+        assert!(!shoe.cut_card_reached());
+
+        // The dealer only reshuffles between rounds
+        let config = game::GameConfig {
+            penetration: 0.5,
+            burn_cards: 1,
+            low_cards_threshold: 0,
+            ..game::DEFAULT_CONFIG
+        };
+        let mut shoe = cards::create_shoe(1);
+        cards::shuffle_deck_seeded(&mut shoe, 21);
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let mut dealer = Dealer::new(shoe, config, callback);
+        dealer.set_seed(21);
+        dealer.players_mut().push(Player::new(1000));
+        let mut reshuffles = 0;
+        for _ in 0..20 {
+            if dealer.start_round(true).contains(&DealerRequest::LowCards) {
+                reshuffles += 1;
+                assert_eq!(dealer.shoe().dealt(), 1);
+                assert_eq!(dealer.shoe().remaining(), 51);
+            }
+            while let Some(decision) = dealer.pending() {
+                let action = match decision {
+                    Decision::Bet { .. } => PlayerAction::Bet(10),
+                    Decision::Play { .. } => PlayerAction::Stand,
+                    _ => PlayerAction::None,
+                };
+                for event in dealer.apply(action).unwrap() {
+                    assert_ne!(event, DealerRequest::LowCards);
+                }
+            }
+            // No cards are lost
+            let table: usize = dealer.hand().len()
+                + dealer.players()[0]
+                    .hands()
+                    .iter()
+                    .map(|hand| hand.len())
+                    .sum::<usize>();
+            assert_eq!(
+                dealer.shoe().remaining() + dealer.shoe().discards().len() + table,
+                52
+            );
+        }
+        assert!(reshuffles > 0);
+    }
+
+    #[test]
+    fn continuous_shoe_tests() {
+        // Discards are reinserted without losing any cards
+        for &random_positions in [false, true].iter() {
+            let mut shoe = shoe::create_shoe(1, ShuffleMode::Continuous { random_positions });
+            let drawn: Vec<Card> = (0..10).map(|_| shoe.draw().unwrap()).collect();
+            shoe.discard(drawn);
+            shoe.reinsert_discards(&mut StdRng::seed_from_u64(21));
+            assert_eq!((shoe.remaining(), shoe.dealt()), (52, 0));
+            assert!(shoe.discards().is_empty());
+            let mut cards: Vec<Card> = shoe.cards().iter().copied().collect();
+            cards.sort();
+            assert_eq!(cards, cards::create_deck());
+        }
+
+        // The dealer reinserts discards every round instead of reshuffling
+        let config = game::GameConfig {
+            shuffle_mode: ShuffleMode::Continuous {
+                random_positions: true,
+            },
+            ..game::DEFAULT_CONFIG
+        };
+        let mut shoe = cards::create_shoe(2);
+        cards::shuffle_deck_seeded(&mut shoe, 21);
+        let callback = |request: DealerRequest, _: Option<&Player>, _: &Dealer| {
+            assert_ne!(request, DealerRequest::LowCards);
+            match request {
+                DealerRequest::Bet => PlayerAction::Bet(10),
+                DealerRequest::Play(_) => PlayerAction::Stand,
+                _ => PlayerAction::None,
+            }
+        };
+        let mut dealer = Dealer::new(shoe, config, callback);
+        dealer.set_seed(21);
+        dealer.players_mut().push(Player::new(10000));
+        for _ in 0..100 {
+            dealer.play_round(true);
+            let table = dealer.hand().len() + dealer.players()[0].hands()[0].len();
+            assert_eq!(dealer.shoe().remaining() + table, 104);
+            assert!(!dealer.shoe().cut_card_reached());
+        }
+    }
+
+    #[test]
+    fn shuffle_procedure_tests() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let deck = cards::create_deck();
+
+        // A riffle keeps the order of each half, leaving at most two rising sequences
+        let mut riffled = deck.clone();
+        shuffle::riffle(&mut riffled, &mut rng);
+        let positions: Vec<usize> = deck
+            .iter()
+            .map(|card| riffled.iter().position(|other| other == card).unwrap())
+            .collect();
+        let rising_sequences = 1 + positions
+            .windows(2)
+            .filter(|pair| pair[0] > pair[1])
+            .count();
+        assert!(rising_sequences <= 2);
+        assert_ne!(riffled, deck);
+
+        // A cut moves the top of the deck to the bottom
+        let mut cut = deck.clone();
+        shuffle::cut(&mut cut, &mut rng);
+        let position = deck.iter().position(|&card| card == cut[0]).unwrap();
+        assert_eq!(cut[..52 - position], deck[position..]);
+        assert_eq!(cut[52 - position..], deck[..position]);
+
+        // Strip and box shuffles keep the cards in each packet together
+        for &step in [
+            ShuffleStep::Strip { packets: 4 },
+            ShuffleStep::Box { packets: 4 },
+        ]
+        .iter()
+        {
+            let mut shuffled = deck.clone();
+            ShuffleProcedure::new(vec![step]).apply(&mut shuffled, &mut rng);
+            let breaks = shuffled.windows(2).filter(|pair| pair[0] > pair[1]).count();
+            assert!(breaks <= 3);
+        }
+
+        // Procedures keep every card and are reproducible with a seed
+        for procedure in [
+            ShuffleProcedure::riffle_riffle_strip_riffle_cut(),
+            ShuffleProcedure::seven_riffles(),
+            ShuffleProcedure::riffle_box_riffle_cut(),
+        ]
+        .iter()
+        {
+            let mut first = cards::create_shoe(6);
+            let mut second = cards::create_shoe(6);
+            procedure.apply_seeded(&mut first, 21);
+            procedure.apply_seeded(&mut second, 21);
+            assert_eq!(first, second);
+            assert_ne!(first, cards::create_shoe(6));
+            let mut expected = cards::create_shoe(6);
+            expected.sort();
+            first.sort();
+            assert_eq!(first, expected);
+        }
+    }
+
+    #[test]
+    fn stacked_deck_tests() {
+        // Shoes parsed from a string deal in order
+        let mut shoe: Shoe = "AS KH, 8D 10C".parse().unwrap();
+        assert_eq!(shoe.len(), 4);
+        for card in stacked("AS KH 8D TC") {
+            assert_eq!(shoe.draw(), Some(card));
+        }
+        assert_eq!(shoe.draw(), None);
+        assert_eq!(
+            "AS KX".parse::<Shoe>(),
+            Err(cards::ParseCardError::InvalidSuit('X'))
+        );
+
+        // Stacked cards are moved to the top without changing the size of the shoe
+        let mut shoe = Shoe::new(1);
+        shoe.stack(stacked("AS AS KH"));
+        assert_eq!(shoe.len(), 53);
+        assert_eq!(
+            shoe.cards()
+                .iter()
+                .filter(|&&card| card == stacked("KH")[0])
+                .count(),
+            1
+        );
+        for card in stacked("AS AS KH 2S") {
+            assert_eq!(shoe.draw(), Some(card));
+        }
+        // Stacked cards in the discard tray are taken out of it rather than copied
+        let mut shoe: Shoe = "AS KH 8D".parse().unwrap();
+        shoe.burn(1);
+        shoe.stack(stacked("AS"));
+        assert!(shoe.discards().is_empty());
+        assert_eq!(shoe.len(), 3);
+        assert_eq!(shoe.remaining(), 3);
+
+        // Stacked shoes are dealt in order, even though they are below the low card threshold
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let mut dealer = Dealer::new(stacked("TH 7D 9S 8C KS"), game::DEFAULT_CONFIG, callback);
+        dealer.players_mut().push(Player::new(100));
+        assert!(dealer.start_round(true).is_empty());
+        dealer.apply(PlayerAction::Bet(10)).unwrap();
+        assert_eq!(dealer.players()[0].hands()[0].cards(), &stacked("TH 9S"));
+        assert_eq!(dealer.hand(), &stacked("7D 8C"));
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(*dealer.players()[0].money(), 110);
+        // Once dealt from, the shoe is reshuffled as usual
+        assert!(dealer.start_round(true).contains(&DealerRequest::LowCards));
+
+        // Running out of cards in the middle of a round is announced too
+        let mut dealer = dealt(game::DEFAULT_CONFIG, "TH 7D 5S 8C");
+        assert_eq!(dealer.players()[0].hands()[0].cards(), &stacked("TH 5S"));
+        let events = dealer.apply(PlayerAction::Hit).unwrap();
+        assert_eq!(events.first(), Some(&DealerRequest::LowCards));
+        assert_eq!(dealer.players()[0].hands()[0].cards().len(), 3);
+
+        // Rigged cards are dealt to the right positions, even after a reshuffle
+        let config = game::GameConfig {
+            low_cards_threshold: 312,
+            ..game::DEFAULT_CONFIG
+        };
+        let mut dealer = Dealer::new(Shoe::new(6), config, callback);
+        dealer.set_seed(21);
+        // Shoes are only reshuffled once they have been dealt from
+        dealer.shoe_mut().draw();
+        dealer.players_mut().push(Player::new(100));
+        dealer.players_mut().push(Player::new(100));
+        let pair = |cards: &str| {
+            let cards = stacked(cards);
+            [cards[0], cards[1]]
+        };
+        dealer.rig(
+            &[pair("8D 8C"), pair("AH KH")],
+            pair("AS 6D"),
+            &stacked("3H"),
+        );
+        assert!(dealer.start_round(true).contains(&DealerRequest::LowCards));
+        dealer.apply(PlayerAction::Bet(10)).unwrap();
+        assert_eq!(
+            dealer.apply(PlayerAction::Bet(10)).unwrap(),
+            vec![DealerRequest::UpCard(stacked("AS")[0])]
+        );
+        assert_eq!(dealer.hand(), &stacked("AS 6D"));
+        assert_eq!(dealer.players()[0].hands()[0].cards(), &stacked("8D 8C"));
+        assert_eq!(dealer.players()[1].hands()[0].cards(), &stacked("AH KH"));
+        // Insurance is offered against the ace
+        assert_eq!(dealer.pending(), Some(Decision::Insurance { player: 0 }));
+        dealer.apply(PlayerAction::None).unwrap();
+        dealer.apply(PlayerAction::None).unwrap();
+        dealer.apply(PlayerAction::Hit).unwrap();
+        assert_eq!(dealer.players()[0].hands()[0].cards(), &stacked("8D 8C 3H"));
+
+        // The pairs of players who sit out are not dealt
+        let mut dealer = Dealer::new(Shoe::new(6), game::DEFAULT_CONFIG, callback);
+        dealer.players_mut().push(Player::new(100));
+        dealer.players_mut().push(Player::new(100));
+        dealer.rig(
+            &[pair("8D 8C"), pair("AH KH")],
+            pair("9S 7D"),
+            &stacked("3H"),
+        );
+        dealer.start_round(true);
+        dealer.apply(PlayerAction::None).unwrap();
+        dealer.apply(PlayerAction::Bet(10)).unwrap();
+        assert!(dealer.players()[0].hands().is_empty());
+        assert_eq!(dealer.players()[1].hands()[0].cards(), &stacked("AH KH"));
+        assert_eq!(dealer.hand(), &stacked("9S 7D"));
+
+        // The dealer's second card comes after the hits in no-hole-card games
+        let config = game::GameConfig {
+            hole_card: HoleCardRule::NoHoleCard {
+                original_bets_only: true,
+            },
+            ..game::DEFAULT_CONFIG
+        };
+        let mut dealer = Dealer::new(Shoe::new(6), config, callback);
+        dealer.players_mut().push(Player::new(100));
+        dealer.rig(&[pair("TD 6C")], pair("9S TS"), &stacked("2H"));
+        dealer.start_round(true);
+        dealer.apply(PlayerAction::Bet(10)).unwrap();
+        assert_eq!(dealer.hand(), &stacked("9S"));
+        dealer.apply(PlayerAction::Hit).unwrap();
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(dealer.hand(), &stacked("9S TS"));
+        assert_eq!(*dealer.players()[0].money(), 90);
+    }
+
+    #[test]
+    fn dealer_play_tests() {
+        let s17 = game::DEFAULT_CONFIG;
+        let h17 = game::GameConfig {
+            stand_soft_17: false,
+            ..s17
+        };
+
+        // Every hard and soft total, with whether the dealer hits under S17 and H17
+        let table = [
+            // Hard totals
+            ("2S 2H", true, true),
+            ("2S 3H", true, true),
+            ("2S 4H", true, true),
+            ("2S 5H", true, true),
+            ("2S 6H", true, true),
+            ("2S 7H", true, true),
+            ("2S 8H", true, true),
+            ("2S 9H", true, true),
+            ("TS 2H", true, true),
+            ("TS 3H", true, true),
+            ("TS 4H", true, true),
+            ("TS 5H", true, true),
+            ("TS 6H", true, true),
+            ("TS 7H", false, false),
+            ("TS 8H", false, false),
+            ("TS 9H", false, false),
+            ("TS KH", false, false),
+            ("TS 5H 6D", false, false),
+            // Aces counted as 1
+            ("AS 5H TD", true, true),
+            ("AS 6H KD", false, false),
+            ("AS AH 5D TC", false, false),
+            // Soft totals
+            ("AS AH", true, true),
+            ("AS 2H", true, true),
+            ("AS 3H", true, true),
+            ("AS 4H", true, true),
+            ("AS 5H", true, true),
+            ("AS 6H", false, true),
+            ("AS 7H", false, false),
+            ("AS 8H", false, false),
+            ("AS 9H", false, false),
+            ("AS KH", false, false),
+            // Soft totals with more than two cards
+            ("AS 2H 4D", false, true),
+            ("AS AH 5D", false, true),
+            ("AS AH AD 4C", false, true),
+            ("AS 2H 5D", false, false),
+            ("AS 5H 5D", false, false),
+        ];
+        for &(hand, s17_hits, h17_hits) in table.iter() {
+            let hand = stacked(hand);
+            assert_eq!(game::dealer_hits(&hand, &s17), s17_hits, "S17 {:?}", hand);
+            assert_eq!(game::dealer_hits(&hand, &h17), h17_hits, "H17 {:?}", hand);
+        }
+
+        // Dealer play continues through multiple soft and hard totals
+        let play = |hand: &str, shoe: &str, rules| {
+            let mut hand = stacked(hand);
+            let mut shoe: Shoe = shoe.parse().unwrap();
+            game::dealer_play(&mut hand, &mut shoe, &rules);
+            hand
+        };
+        // Soft 17 to hard 12, which must be hit again
+        assert_eq!(play("AS 6H", "5D 5C 9H", h17), stacked("AS 6H 5D 5C"));
+        assert_eq!(play("AS 6H", "5D 5C 9H", s17), stacked("AS 6H"));
+        // Soft 17 to another soft 17
+        assert_eq!(play("AS 2H", "4D AC TH", s17), stacked("AS 2H 4D"));
+        assert_eq!(play("AS 2H", "4D AC TH", h17), stacked("AS 2H 4D AC"));
+        // Soft 16 to soft 17 to hard 17
+        assert_eq!(play("AS 3H", "2D AC TH", s17), stacked("AS 3H 2D AC"));
+        assert_eq!(play("AS 3H", "2D AC TH", h17), stacked("AS 3H 2D AC TH"));
+        // One-card hands are dealt a second card first
+        assert_eq!(play("AS", "6H 2C", s17), stacked("AS 6H"));
+        // Stops when the shoe runs out
+        assert_eq!(play("TS 2H", "3D", s17), stacked("TS 2H 3D"));
+
+        // The dealer keeps hitting after a soft 17 in a round
+        let config = game::GameConfig {
+            insurance: false,
+            low_cards_threshold: 0,
+            ..h17
+        };
+        let mut dealer = dealt(config, "TH AD 8C 6S 5D 5C 9H");
+        dealer.apply(PlayerAction::Stand).unwrap();
+        assert_eq!(dealer.hand(), &stacked("AD 6S 5D 5C"));
+        // Player's 18 beats the dealer's 17
+        assert_eq!(*dealer.players()[0].money(), 110);
+    }
+
+    #[test]
+    fn hand_value_tests() {
+        let value = |hand: &str| game::HandValue::new(&stacked(hand));
+
+        // Soft and hard totals
+        assert_eq!((value("AS 7H").total, value("AS 7H").soft), (18, true));
+        assert_eq!((value("TS 8H").total, value("TS 8H").soft), (18, false));
+        assert_eq!(
+            (value("AS 7H TD").total, value("AS 7H TD").soft),
+            (18, false)
+        );
+        assert_eq!((value("AS AH").total, value("AS AH").soft), (12, true));
+        assert_eq!(
+            (value("AS AH TD").total, value("AS AH TD").soft),
+            (12, false)
+        );
+        assert_eq!(value("AS 7H").hard_total(), 8);
+        assert_eq!(value("AS 7H").to_string(), "soft 18");
+        assert_eq!(value("TS 8H").to_string(), "hard 18");
+        assert_eq!(value("").total, 0);
+
+        // Blackjacks, busts and pairs
+        assert!(value("AS KH").is_blackjack);
+        assert!(!value("AS 5H 5D").is_blackjack);
+        assert!(value("TS 5H 7D").is_bust);
+        assert!(!value("TS AH AD").is_bust);
+        assert!(value("8S 8H").is_pair);
+        assert!(!value("KS QH").is_pair);
+        assert!(!value("8S 8H 8D").is_pair);
+
+        // Aces are counted as 1 when they would bust the hand, in any order
+        assert_eq!(game::get_hand_value(&stacked("TS AH AD"), true), 12);
+        assert_eq!(game::get_hand_value(&stacked("AH AD TS"), true), 12);
+        assert_eq!(game::get_hand_value(&stacked("AH AD"), false), 22);
+
+        // Hands share the evaluator
+        let mut hand = game::Hand::new(10);
+        hand.cards_mut().extend(stacked("AS 6H"));
+        assert_eq!(hand.value(), value("AS 6H"));
+    }
+
+    #[test]
+    fn strategy_tests() {
+        let config = game::DEFAULT_CONFIG;
+        let rules = |change: fn(&mut game::GameConfig)| {
+            let mut config = config;
+            change(&mut config);
+            BasicStrategy::new(&config)
+        };
+        let s17 = BasicStrategy::new(&config);
+        let h17 = rules(|c| c.stand_soft_17 = false);
+        let no_das = rules(|c| c.double_after_split = false);
+        let single_deck = rules(|c| c.shoe_deck_count = 1);
+        let late = rules(|c| c.surrender = SurrenderRule::Late);
+        let late_h17 = rules(|c| {
+            c.surrender = SurrenderRule::Late;
+            c.stand_soft_17 = false;
+        });
+        let early = rules(|c| c.surrender = SurrenderRule::Early);
+        let no_peek = rules(|c| {
+            c.hole_card = HoleCardRule::NoHoleCard {
+                original_bets_only: false,
+            }
+        });
+
+        // Hard totals
+        assert_eq!(s17.hard(8, 6), Action::Hit);
+        assert_eq!(single_deck.hard(8, 6), Action::DoubleOrHit);
+        assert_eq!(s17.hard(9, 2), Action::Hit);
+        assert_eq!(single_deck.hard(9, 2), Action::DoubleOrHit);
+        assert_eq!(s17.hard(10, 9), Action::DoubleOrHit);
+        assert_eq!(s17.hard(10, 10), Action::Hit);
+        assert_eq!(s17.hard(11, 11), Action::Hit);
+        assert_eq!(h17.hard(11, 11), Action::DoubleOrHit);
+        assert_eq!(no_peek.hard(11, 10), Action::Hit);
+        assert_eq!(s17.hard(12, 3), Action::Hit);
+        assert_eq!(s17.hard(12, 4), Action::Stand);
+        assert_eq!(s17.hard(16, 7), Action::Hit);
+        assert_eq!(s17.hard(17, 11), Action::Stand);
+
+        // Surrender
+        assert_eq!(s17.hard(16, 10), Action::Hit);
+        assert_eq!(late.hard(16, 10), Action::SurrenderOrHit);
+        assert_eq!(late.hard(15, 11), Action::Hit);
+        assert_eq!(late_h17.hard(15, 11), Action::SurrenderOrHit);
+        assert_eq!(late_h17.hard(17, 11), Action::SurrenderOrStand);
+        assert_eq!(late.pair(8, 11), Action::Split);
+        assert_eq!(late_h17.pair(8, 11), Action::SurrenderOrSplit);
+        assert_eq!(early.hard(6, 11), Action::SurrenderOrHit);
+        assert_eq!(early.hard(14, 10), Action::SurrenderOrHit);
+        assert_eq!(early.pair(8, 10), Action::SurrenderOrSplit);
+
+        // Soft totals
+        assert_eq!(s17.soft(13, 4), Action::Hit);
+        assert_eq!(single_deck.soft(13, 4), Action::DoubleOrHit);
+        assert_eq!(s17.soft(17, 3), Action::DoubleOrHit);
+        assert_eq!(s17.soft(18, 2), Action::Stand);
+        assert_eq!(h17.soft(18, 2), Action::DoubleOrStand);
+        assert_eq!(s17.soft(18, 8), Action::Stand);
+        assert_eq!(s17.soft(18, 9), Action::Hit);
+        assert_eq!(s17.soft(19, 6), Action::Stand);
+        assert_eq!(h17.soft(19, 6), Action::DoubleOrStand);
+
+        // Pairs
+        assert_eq!(s17.pair(2, 2), Action::Split);
+        assert_eq!(no_das.pair(2, 2), Action::Hit);
+        assert_eq!(s17.pair(4, 5), Action::Split);
+        assert_eq!(no_das.pair(4, 5), Action::Hit);
+        assert_eq!(s17.pair(5, 6), Action::DoubleOrHit);
+        assert_eq!(s17.pair(9, 7), Action::Stand);
+        assert_eq!(s17.pair(9, 8), Action::Split);
+        assert_eq!(s17.pair(10, 6), Action::Stand);
+        assert_eq!(s17.pair(11, 11), Action::Split);
+        assert_eq!(no_peek.pair(11, 11), Action::Hit);
+        assert_eq!(no_peek.pair(8, 10), Action::Hit);
+
+        // Resolving actions that aren't available
+        let all = Available {
+            double: true,
+            split: true,
+            surrender: true,
+        };
+        let none = Available {
+            double: false,
+            split: false,
+            surrender: false,
+        };
+        let up = stacked("TD")[0];
+        let action = |strategy: &BasicStrategy, hand: &str, up, available| {
+            strategy.action(&stacked(hand), up, available)
+        };
+        assert_eq!(action(&late, "TS 6H", up, all), PlayerAction::Surrender);
+        assert_eq!(action(&late, "TS 6H", up, none), PlayerAction::Hit);
+        assert_eq!(action(&late, "8S 8H", up, all), PlayerAction::Split);
+        assert_eq!(action(&late, "8S 8H", up, none), PlayerAction::Hit);
+        let up = stacked("5D")[0];
+        assert_eq!(action(&s17, "AS 7H", up, all), PlayerAction::DoubleDown);
+        assert_eq!(action(&s17, "AS 4H 3C", up, none), PlayerAction::Stand);
+        assert_eq!(action(&s17, "AS 2H", up, none), PlayerAction::Hit);
+        assert_eq!(action(&s17, "5S 5H", up, all), PlayerAction::DoubleDown);
+        assert_eq!(action(&s17, "TS TH", up, all), PlayerAction::Stand);
+
+        // Charts list every row
+        let chart = s17.to_string();
+        assert_eq!(chart.lines().count(), 3 + 18 + 10 + 10);
+        assert!(chart.contains("A,7     S Ds Ds Ds Ds  S  S  H  H  H"));
+
+        // The agent plays whole games without making invalid decisions
+        struct Checked(BasicStrategyAgent);
+        impl PlayerAgent for Checked {
+            fn bet(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+                self.0.bet(player, dealer)
+            }
+            fn play(&mut self, hand: usize, player: &Player, dealer: &Dealer) -> PlayerAction {
+                self.0.play(hand, player, dealer)
+            }
+            fn surrender(&mut self, player: &Player, dealer: &Dealer) -> PlayerAction {
+                self.0.surrender(player, dealer)
+            }
+            fn error(&mut self, error: PlayerActionError, _: &Player, _: &Dealer) {
+                panic!("{:?}", error);
+            }
+        }
+        for &surrender in [
+            SurrenderRule::None,
+            SurrenderRule::Late,
+            SurrenderRule::Early,
+        ]
+        .iter()
+        {
+            let config = game::GameConfig {
+                surrender,
+                ..game::DEFAULT_CONFIG
+            };
+            let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+            let mut shoe = cards::create_shoe(6);
+            cards::shuffle_deck_seeded(&mut shoe, 21);
+            let mut dealer = Dealer::new(shoe, config, callback);
+            dealer.set_seed(21);
+            let agent = Checked(BasicStrategyAgent::new(&config, 10));
+            dealer
+                .players_mut()
+                .push(Player::with_agent(100_000, agent));
+            let mut outcomes = Vec::new();
+            for _ in 0..500 {
+                let result = dealer.play_round(true);
+                outcomes.extend(result.players[0].hands.iter().map(|hand| hand.outcome));
+            }
+            assert!(outcomes.len() > 500);
+            assert_eq!(
+                outcomes.contains(&Outcome::Surrender),
+                surrender != SurrenderRule::None
+            );
+        }
+
+        // Players who can't afford the bet sit out instead of being asked forever
+        let config = game::DEFAULT_CONFIG;
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let mut dealer = Dealer::new(Shoe::new(6), config, callback);
+        let agent = BasicStrategyAgent::new(&config, 10);
+        dealer.players_mut().push(Player::with_agent(5, agent));
+        let result = dealer.play_round(true);
+        assert!(result.players[0].hands.is_empty());
+        assert_eq!(*dealer.players()[0].money(), 5);
+    }
+
+    #[test]
+    fn analysis_tests() {
+        let config = game::GameConfig {
+            surrender: SurrenderRule::Late,
+            ..game::DEFAULT_CONFIG
+        };
+        let mut analyzer = Analyzer::new(&config);
+        let shoe_without = |cards: &str| {
+            let mut shoe = Composition::from(&Shoe::new(6));
+            for card in stacked(cards) {
+                assert!(shoe.remove(card));
+            }
+            shoe
+        };
+
+        // Dealer probabilities add up, and match well-known values
+        for up in 2..=11 {
+            let up_card = Card::new(
+                Rank::ALL
+                    .iter()
+                    .copied()
+                    .find(|rank| rank.value() == up)
+                    .unwrap(),
+                Suit::Spades,
+            );
+            let shoe = shoe_without(&up_card.to_string());
+            let odds = analyzer.dealer_probabilities(up_card, &shoe);
+            let sum: f64 = odds.totals.iter().sum::<f64>() + odds.bust + odds.blackjack;
+            assert!((sum - 1.0).abs() < 1e-9);
+            assert_eq!(odds.total(16), 0.0);
+        }
+        let odds = analyzer.dealer_probabilities(stacked("6S")[0], &shoe_without("6S"));
+        assert!((odds.bust - 0.42).abs() < 0.01);
+        let odds = analyzer.dealer_probabilities(stacked("AS")[0], &shoe_without("AS"));
+        assert!((odds.blackjack - 96.0 / 311.0).abs() < 1e-9);
+
+        // Player expected values
+        let values = |analyzer: &mut Analyzer, hand: &str, up: &str| {
+            let shoe = shoe_without(&format!("{} {}", hand, up));
+            analyzer.expected_values(&stacked(hand), stacked(up)[0], &shoe)
+        };
+        let sixteen = values(&mut analyzer, "TS 6H", "TD");
+        assert!((sixteen.stand - -0.54).abs() < 0.02);
+        assert!(sixteen.hit > sixteen.stand);
+        assert_eq!(sixteen.surrender, Some(-0.5));
+        assert_eq!(sixteen.best(), PlayerAction::Surrender);
+        assert_eq!(
+            values(&mut analyzer, "6S 5H", "6D").best(),
+            PlayerAction::DoubleDown
+        );
+        assert_eq!(
+            values(&mut analyzer, "8S 8H", "6D").best(),
+            PlayerAction::Split
+        );
+        assert_eq!(
+            values(&mut analyzer, "TS TH", "6D").best(),
+            PlayerAction::Stand
+        );
+        let three_cards = values(&mut analyzer, "TS 2H 4C", "6D");
+        assert_eq!((three_cards.double, three_cards.surrender), (None, None));
+
+        // Values match the engine's payouts when the outcome is certain
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let certain = [
+            // Dealer makes 19
+            ("TS 8H", "TD", "9S 9H 9D", PlayerAction::Stand, -1.0),
+            ("TS 8H", "TD", "9S 9H 9D", PlayerAction::DoubleDown, -2.0),
+            // Dealer busts
+            ("TS 9H", "6D", "TH TC TD", PlayerAction::Stand, 1.0),
+            ("5S 5H", "6D", "TH TC TD", PlayerAction::DoubleDown, 2.0),
+            // Player blackjack
+            ("AS KH", "9D", "9S 9H 9C", PlayerAction::Stand, 1.5),
+        ];
+        for &(hand, up, rest, action, expected) in certain.iter() {
+            let shoe = Composition::new(&stacked(rest));
+            let values = analyzer.expected_values(&stacked(hand), stacked(up)[0], &shoe);
+            let value = match action {
+                PlayerAction::Stand => values.stand,
+                _ => values.double.unwrap(),
+            };
+            assert!((value - expected).abs() < 1e-9);
+
+            let hand = stacked(hand);
+            let rest = stacked(rest);
+            let mut dealer = Dealer::new(Shoe::new(1), config, callback);
+            dealer.players_mut().push(Player::new(100));
+            dealer.rig(&[[hand[0], hand[1]]], [stacked(up)[0], rest[0]], &rest[1..]);
+            dealer.start_round(true);
+            dealer.apply(PlayerAction::Bet(10)).unwrap();
+            if dealer.pending().is_some() {
+                dealer.apply(action).unwrap();
+            }
+            let net = dealer.result().unwrap().players[0].net();
+            assert_eq!(net as f64, expected * 10.0);
+        }
+
+        // Unseen cards include the dealer's hole card
+        let mut dealer = Dealer::new(Shoe::new(1), config, callback);
+        dealer.players_mut().push(Player::new(100));
+        dealer.start_round(true);
+        dealer.apply(PlayerAction::Bet(10)).unwrap();
+        assert_eq!(
+            Composition::unseen(&dealer).len(),
+            dealer.shoe().remaining() + 1
+        );
+    }
+
+    #[test]
+    fn sim_tests() {
+        let config = game::DEFAULT_CONFIG;
+        let agent = || BasicStrategyAgent::new(&config, 10);
+        let stats = sim::simulate(&config, agent(), 2000, 21);
+        assert_eq!(stats.rounds, 2000);
+        assert_eq!(stats.wagered, 20000);
+        assert!(stats.hands >= stats.rounds);
+        assert_eq!(
+            stats.wins
+                + stats.losses
+                + stats.pushes
+                + stats.blackjacks
+                + stats.surrenders
+                + stats.busts,
+            stats.hands
+        );
+        let rates = stats.win_rate() + stats.loss_rate() + stats.push_rate();
+        assert!((rates - 1.0).abs() < 1e-9);
+        assert!(stats.blackjack_rate() > 0.02 && stats.blackjack_rate() < 0.08);
+        assert!(stats.std_dev() > 0.9 && stats.std_dev() < 1.4);
+        let (low, high) = stats.confidence_interval(1.96);
+        assert!(low < stats.ev() && stats.ev() < high);
+        assert!(low < 0.0 && high > -0.01);
+
+        // The same seed gives the same statistics
+        assert_eq!(sim::simulate(&config, agent(), 2000, 21), stats);
+        assert_ne!(sim::simulate(&config, agent(), 2000, 22), stats);
+
+        // Merging statistics adds them together
+        let mut merged = Statistics::new();
+        merged.merge(&stats);
+        merged.merge(&stats);
+        assert_eq!(merged.rounds, 4000);
+        assert_eq!(merged.net, stats.net * 2);
+        assert!((merged.ev() - stats.ev()).abs() < 1e-9);
+
+        // Large results are squared without overflowing
+        let mut stats = Statistics::new();
+        let result = game::PlayerResult {
+            hands: vec![game::HandResult {
+                outcome: game::Outcome::Loss,
+                wagered: i32::MAX,
+                net: i32::MIN,
+            }],
+            bet: i32::MAX,
+            insurance: 0,
+            side_bets: Vec::new(),
+        };
+        stats.record(&result);
+        stats.record(&result);
+        assert_eq!(stats.net, 2 * i32::MIN as i64);
+        assert_eq!(stats.net_squared, 2 * (i32::MIN as i128).pow(2) as u128);
+    }
+
+    #[test]
+    fn parallel_sim_tests() {
+        let config = game::DEFAULT_CONFIG;
+        let agent = |_| BasicStrategyAgent::new(&config, 10);
+        let stats = sim::simulate_parallel(&config, agent, 1001, 21, 4);
+        assert_eq!(stats.rounds, 1001);
+        assert_eq!(stats.wagered, 10010);
+
+        // The same seed and thread count give identical totals
+        assert_eq!(sim::simulate_parallel(&config, agent, 1001, 21, 4), stats);
+        assert_ne!(sim::simulate_parallel(&config, agent, 1001, 22, 4), stats);
+
+        // More threads than rounds, and no threads
+        assert_eq!(sim::simulate_parallel(&config, agent, 3, 21, 8).rounds, 3);
+        assert_eq!(sim::simulate_parallel(&config, agent, 10, 21, 0).rounds, 10);
+    }
+
+    #[test]
+    fn counting_tests() {
+        // Tags for 2 to 9, ten and ace
+        let tags = |system: &dyn CountingSystem| -> Vec<f32> {
+            stacked("2S 3S 4S 5S 6S 7S 8S 9S TS AS")
+                .iter()
+                .map(|card| system.tag(card.rank))
+                .collect()
+        };
+        assert_eq!(
+            tags(&HiLo),
+            vec![1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0, -1.0]
+        );
+        assert_eq!(
+            tags(&KnockOut),
+            vec![1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, -1.0, -1.0]
+        );
+        assert_eq!(
+            tags(&OmegaII),
+            vec![1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 0.0, -1.0, -2.0, 0.0]
+        );
+        assert_eq!(
+            tags(&Zen),
+            vec![1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 0.0, 0.0, -2.0, -1.0]
+        );
+        assert_eq!(
+            tags(&WongHalves),
+            vec![0.5, 1.0, 1.0, 1.5, 1.0, 0.5, 0.0, -0.5, -1.0, -1.0]
+        );
+        assert!(HiLo.balanced() && OmegaII.balanced() && Zen.balanced() && WongHalves.balanced());
+        assert!(!KnockOut.balanced());
+
+        // Counting a whole shoe ends at zero, or at 4 for KO
+        let mut counter = Counter::new(KnockOut, 6);
+        assert_eq!(counter.running_count(), -20.0);
+        for card in cards::create_shoe(6) {
+            counter.count(card);
+        }
+        assert_eq!(counter.running_count(), 4.0);
+        counter.reset();
+        assert_eq!(counter.running_count(), -20.0);
+
+        // Counting the table sees every card once, and the hole card only at the end
+        let config = game::GameConfig {
+            burn_cards: 0,
+            ..game::DEFAULT_CONFIG
+        };
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let mut shoe = cards::create_shoe(6);
+        cards::shuffle_deck_seeded(&mut shoe, 21);
+        let mut dealer = Dealer::new(shoe, config, callback);
+        dealer.players_mut().push(Player::new(1000));
+        dealer.players_mut().push(Player::new(1000));
+        let mut counter = Counter::new(HiLo, 6);
+        let hilo = |cards: &[Card]| cards.iter().map(|card| HiLo.tag(card.rank)).sum::<f32>();
+        for _ in 0..10 {
+            let mut events = dealer.start_round(true);
+            loop {
+                for event in events.iter() {
+                    counter.observe(event, &dealer);
+                }
+                // Updating again between events doesn't count cards twice
+                counter.update(&dealer);
+                let action = match dealer.pending() {
+                    Some(Decision::Bet { .. }) => PlayerAction::Bet(10),
+                    Some(Decision::Play { player, hand }) => {
+                        // Only the shoe and the hole card are unseen
+                        let mut unseen: Vec<Card> = dealer.shoe().cards().iter().copied().collect();
+                        unseen.push(dealer.hand()[1]);
+                        assert_eq!(counter.running_count(), -hilo(&unseen));
+                        if dealer.players()[player].hands()[hand].value().total < 15 {
+                            PlayerAction::Hit
+                        } else {
+                            PlayerAction::Stand
+                        }
+                    }
+                    Some(_) => PlayerAction::None,
+                    None => break,
+                };
+                events = dealer.apply(action).unwrap();
+            }
+            let remaining: Vec<Card> = dealer.shoe().cards().iter().copied().collect();
+            assert_eq!(counter.running_count(), -hilo(&remaining));
+        }
+        let decks = dealer.shoe().remaining() as f32 / 52.0;
+        assert!((counter.true_count(dealer.shoe()) - counter.running_count() / decks).abs() < 1e-4);
+
+        // Observing only the dealer's events keeps the count across rounds
+        let mut shoe = cards::create_shoe(6);
+        cards::shuffle_deck_seeded(&mut shoe, 21);
+        let mut dealer = Dealer::new(shoe, config, callback);
+        dealer.players_mut().push(Player::new(1000));
+        let mut counter = Counter::new(HiLo, 6);
+        for _ in 0..20 {
+            let mut events = dealer.start_round(true);
+            loop {
+                for event in events.iter() {
+                    counter.observe(event, &dealer);
+                }
+                let action = match dealer.pending() {
+                    Some(Decision::Bet { .. }) => PlayerAction::Bet(10),
+                    Some(Decision::Play { .. }) => PlayerAction::Stand,
+                    Some(_) => PlayerAction::None,
+                    None => break,
+                };
+                events = dealer.apply(action).unwrap();
+            }
+            let remaining: Vec<Card> = dealer.shoe().cards().iter().copied().collect();
+            assert_eq!(counter.running_count(), -hilo(&remaining));
+        }
+    }
+
+    #[test]
+    fn deviations_tests() {
+        let available = Available {
+            double: true,
+            split: true,
+            surrender: true,
+        };
+        let play = |config: &game::GameConfig, deviations: &Deviations, hand, up, tc| {
+            let strategy = BasicStrategy::new(config);
+            deviations.action(&strategy, &stacked(hand), stacked(up)[0], tc, available)
+        };
+
+        // Illustrious 18
+        let s17 = game::DEFAULT_CONFIG;
+        let h17 = game::GameConfig {
+            stand_soft_17: false,
+            ..s17
+        };
+        let i18 = Deviations::illustrious_18(&s17);
+        assert_eq!(i18.plays.len(), 17);
+        assert!(!i18.take_insurance(2.9) && i18.take_insurance(3.0));
+        assert_eq!(play(&s17, &i18, "TS 2H", "4D", -0.5), PlayerAction::Hit);
+        assert_eq!(play(&s17, &i18, "TS 2H", "4D", 0.0), PlayerAction::Stand);
+        assert_eq!(play(&s17, &i18, "TS TH", "6D", 3.0), PlayerAction::Stand);
+        assert_eq!(play(&s17, &i18, "TS TH", "6D", 4.0), PlayerAction::Split);
+        assert_eq!(play(&s17, &i18, "8S 8H", "6D", -5.0), PlayerAction::Split);
+        assert_eq!(play(&s17, &i18, "6S 5H", "AD", 0.0), PlayerAction::Hit);
+        assert_eq!(
+            play(&s17, &i18, "6S 5H", "AD", 1.0),
+            PlayerAction::DoubleDown
+        );
+        let i18_h17 = Deviations::illustrious_18(&h17);
+        assert_eq!(play(&h17, &i18_h17, "6S 5H", "AD", -2.0), PlayerAction::Hit);
+        assert_eq!(
+            play(&h17, &i18_h17, "6S 5H", "AD", 0.0),
+            PlayerAction::DoubleDown
+        );
+
+        // Fab 4, merged with the Illustrious 18
+        assert!(Deviations::fab_4(&s17).plays.is_empty());
+        let late = game::GameConfig {
+            surrender: SurrenderRule::Late,
+            ..s17
+        };
+        let mut deviations = Deviations::illustrious_18(&late);
+        deviations.merge(&Deviations::fab_4(&late));
+        assert_eq!(deviations.plays.len(), 21);
+        assert_eq!(
+            play(&late, &deviations, "TS 5H", "TD", -1.0),
+            PlayerAction::Hit
+        );
+        assert_eq!(
+            play(&late, &deviations, "TS 5H", "TD", 0.0),
+            PlayerAction::Surrender
+        );
+        assert_eq!(
+            play(&late, &deviations, "TS 5H", "TD", 5.0),
+            PlayerAction::Surrender
+        );
+        assert_eq!(
+            play(&late, &deviations, "TS 4H", "TD", 2.0),
+            PlayerAction::Hit
+        );
+        assert_eq!(
+            play(&late, &deviations, "TS 4H", "TD", 3.0),
+            PlayerAction::Surrender
+        );
+        assert_eq!(
+            play(&late, &deviations, "TS 5H", "AD", 1.0),
+            PlayerAction::Surrender
+        );
+        let strategy = BasicStrategy::new(&late);
+        let no_surrender = Available {
+            surrender: false,
+            ..available
+        };
+        let hand = stacked("TS 5H");
+        let up = stacked("TD")[0];
+        assert_eq!(
+            deviations.action(&strategy, &hand, up, 5.0, no_surrender),
+            PlayerAction::Stand
+        );
+
+        // Text format
+        let table: Deviations = "# Comments are ignored
+            insurance >= 3
+            16 v T S >= 0
+            TT v 6 P >= 4
+
+            12 v 4 H < 0
+            A7 v 2 Ds >= 1.5"
+            .parse()
+            .unwrap();
+        assert_eq!(table.insurance, Some(3.0));
+        assert_eq!(
+            table.plays[1],
+            Deviation::at_least(Situation::Pair(10), 6, 4.0, Action::Split)
+        );
+        assert_eq!(
+            table.plays[3],
+            Deviation::at_least(Situation::Soft(18), 2, 1.5, Action::DoubleOrStand)
+        );
+        assert_eq!(table.to_string().parse::<Deviations>().unwrap(), table);
+        assert_eq!(i18.to_string().parse::<Deviations>().unwrap(), i18);
+        assert_eq!(table.plays[2].to_string(), "12 v 4 H < 0");
+        assert_eq!("AA".parse(), Ok(Situation::Pair(11)));
+        assert_eq!(Situation::Soft(12).to_string(), "AA");
+        assert_eq!(Situation::Soft(13).to_string(), "A2");
+        assert_eq!("KT".parse(), Ok(Situation::Pair(10)));
+        let errors = [
+            ("16 T S >= 0", ParseDeviationError::InvalidLength),
+            ("3 v T S >= 0", ParseDeviationError::InvalidHand),
+            ("79 v T S >= 0", ParseDeviationError::InvalidHand),
+            ("16 v X S >= 0", ParseDeviationError::InvalidUpCard),
+            ("16 v T X >= 0", ParseDeviationError::InvalidAction),
+            ("16 v T S > 0", ParseDeviationError::InvalidThreshold),
+            ("16 v T S >= x", ParseDeviationError::InvalidThreshold),
+        ];
+        for &(line, error) in errors.iter() {
+            assert_eq!(line.parse::<Deviation>(), Err(error));
+        }
+
+        // The agent counts the table, and takes insurance and doubles at a high true count
+        let config = game::GameConfig {
+            burn_cards: 0,
+            low_cards_threshold: 0,
+            ..s17
+        };
+        let agent = IndexPlayAgent::new(&config, HiLo, Deviations::illustrious_18(&config), 10);
+        let shoe = Shoe::from(stacked("5S AH 6S 9D 2C 3C 4C 7C 8C 9C TC JC QC KC"));
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let mut dealer = Dealer::new(shoe, config, callback);
+        dealer.players_mut().push(Player::with_agent(100, agent));
+        let result = dealer.play_round(true);
+        assert_eq!(result.players[0].insurance, -5);
+        assert_eq!(result.players[0].hands[0].wagered, 20);
+        // Insurance is taken on a bet too small to halve
+        let agent = IndexPlayAgent::new(&config, HiLo, Deviations::illustrious_18(&config), 1);
+        let shoe = Shoe::from(stacked("5S AH 6S 9D 2C 3C 4C 7C 8C 9C TC JC QC KC"));
+        let mut dealer = Dealer::new(shoe, config, callback);
+        dealer.players_mut().push(Player::with_agent(100, agent));
+        assert_eq!(dealer.play_round(true).players[0].insurance, -1);
+
+        // The agent plays without errors for a whole simulation
+        let agent = IndexPlayAgent::new(&late, HiLo, deviations.clone(), 10);
+        assert_eq!(sim::simulate(&late, agent, 500, 21).rounds, 500);
+
+        // The agent sits out rounds it can't afford, and plays a small bankroll to ruin
+        let agent = IndexPlayAgent::new(&late, HiLo, deviations.clone(), 10);
+        let mut dealer = Dealer::new(Shoe::new(6), late, callback);
+        dealer.players_mut().push(Player::with_agent(5, agent));
+        assert!(dealer.play_round(true).players[0].hands.is_empty());
+        let table = game::GameConfig {
+            min_bet: 10,
+            ..late
+        };
+        let agent = IndexPlayAgent::new(&table, HiLo, deviations, 10);
+        let stats = sim::simulate_with_bankroll(&table, agent, 100_000, 21, 50);
+        assert_eq!(stats.ruins, 1);
+        assert!(stats.rounds < 100_000);
+    }
+
+    #[test]
+    fn betting_tests() {
+        // Progressions follow the results of the last rounds
+        let bets = |policy: &mut dyn BettingPolicy, results: &[Option<i32>]| -> Vec<i32> {
+            results
+                .iter()
+                .map(|&last_net| {
+                    let info = BetInfo {
+                        bankroll: 1000,
+                        last_net,
+                        true_count: 0.0,
+                        min_bet: 10,
+                        max_bet: 100,
+                    };
+                    policy.bet(&info).unwrap()
+                })
+                .collect()
+        };
+        let results = [
+            None,
+            Some(-10),
+            Some(-20),
+            Some(0),
+            Some(40),
+            Some(10),
+            Some(-10),
+        ];
+        assert_eq!(
+            bets(&mut Martingale::new(10), &results),
+            vec![10, 20, 40, 40, 10, 10, 20]
+        );
+        // The doubled bet would go over the table maximum
+        let losses = [None, Some(-10), Some(-20), Some(-40), Some(-80)];
+        assert_eq!(
+            bets(&mut Martingale::new(10), &losses),
+            vec![10, 20, 40, 80, 10]
+        );
+        let wins = [None, Some(10), Some(20), Some(40), Some(10), Some(-20)];
+        assert_eq!(
+            bets(&mut Paroli::new(10), &wins),
+            vec![10, 20, 40, 10, 20, 10]
+        );
+        assert_eq!(
+            bets(&mut OneThreeTwoSix::new(10), &wins),
+            vec![10, 30, 20, 60, 10, 10]
+        );
+        assert_eq!(bets(&mut Flat::new(25), &wins), vec![25; 6]);
+
+        // Kelly bets a fraction of the bankroll when the player has the advantage
+        let mut kelly = Kelly::new(1.0);
+        let mut info = BetInfo {
+            bankroll: 10000,
+            last_net: None,
+            true_count: 0.0,
+            min_bet: 10,
+            max_bet: 1000,
+        };
+        assert_eq!(kelly.bet(&info), Some(10));
+        info.true_count = 3.0;
+        assert_eq!(kelly.bet(&info), Some(76));
+        info.bankroll = 20000;
+        assert_eq!(kelly.bet(&info), Some(153));
+
+        // Players can sit out a round
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let config = game::GameConfig {
+            insurance: false,
+            min_bet: 10,
+            max_bet: 50,
+            ..game::DEFAULT_CONFIG
+        };
+        let mut dealer = Dealer::new(Shoe::new(6), config, callback);
+        dealer.players_mut().push(Player::new(100));
+        dealer.players_mut().push(Player::new(100));
+        dealer.start_round(true);
+        dealer.apply(PlayerAction::None).unwrap();
+        dealer.apply(PlayerAction::Bet(10)).unwrap();
+        while let Some(decision) = dealer.pending() {
+            assert_eq!(decision.player(), 1);
+            dealer.apply(PlayerAction::Stand).unwrap();
+        }
+        let result = dealer.result().unwrap();
+        assert!(result.players[0].hands.is_empty());
+        assert_eq!((result.players[0].bet, result.players[0].net()), (0, 0));
+        assert_eq!(result.players[1].hands.len(), 1);
+        assert_eq!(dealer.players()[0].money(), &100);
+
+        // Players can bet again after sitting out without the table being cleared
+        let mut rounds = 0;
+        let agent = move |request: DealerRequest, _: Option<&Player>, _: &Dealer| match request {
+            DealerRequest::Bet => {
+                rounds += 1;
+                if rounds == 1 {
+                    PlayerAction::None
+                } else {
+                    PlayerAction::Bet(10)
+                }
+            }
+            DealerRequest::Play(_) => PlayerAction::Stand,
+            _ => PlayerAction::None,
+        };
+        let mut dealer = Dealer::new(Shoe::new(6), config, callback);
+        dealer.players_mut().push(Player::with_agent(100, agent));
+        assert!(dealer.play_round(false).players[0].hands.is_empty());
+        let result = dealer.play_round(false);
+        assert_eq!(result.players[0].bet, 10);
+        assert_eq!(result.players[0].hands.len(), 1);
+        assert_eq!(dealer.players()[0].hands()[0].len(), 2);
+
+        // Bets are kept within the table limits and the player's money
+        let stand = |request: DealerRequest, _: Option<&Player>, _: &Dealer| match request {
+            DealerRequest::Play(_) => PlayerAction::Stand,
+            _ => PlayerAction::None,
+        };
+        let first_bet = |money: i32, policy: Flat| {
+            let agent = BettingAgent::new(&config, stand, policy, HiLo);
+            let mut dealer = Dealer::new(Shoe::new(6), config, callback);
+            dealer.players_mut().push(Player::with_agent(money, agent));
+            dealer.play_round(true).players[0].bet
+        };
+        assert_eq!(first_bet(1000, Flat::new(1000)), 50);
+        assert_eq!(first_bet(1000, Flat::new(1)), 10);
+        assert_eq!(first_bet(30, Flat::new(1000)), 30);
+        assert_eq!(first_bet(5, Flat::new(10)), 0);
+
+        // The agent tells the policy how the last round went
+        let agent = BettingAgent::new(&config, stand, Martingale::new(10), HiLo);
+        let mut dealer = Dealer::new(Shoe::new(6), config, callback);
+        dealer.players_mut().push(Player::with_agent(1000, agent));
+        let rounds = [
+            ("TS 6H", "TD 9C", 10),
+            ("TS 9H", "TD 7C", 20),
+            ("TS 9H", "TD 7C", 10),
+        ];
+        for &(player, dealer_cards, bet) in rounds.iter() {
+            let player = stacked(player);
+            let dealer_cards = stacked(dealer_cards);
+            dealer.rig(
+                &[[player[0], player[1]]],
+                [dealer_cards[0], dealer_cards[1]],
+                &[],
+            );
+            assert_eq!(dealer.play_round(true).players[0].bet, bet);
+        }
+
+        // Wonging sits out rounds in simulations
+        let config = game::GameConfig {
+            min_bet: 10,
+            max_bet: 1000,
+            ..game::DEFAULT_CONFIG
+        };
+        let policy = Wonging::new(TrueCountRamp::new(10, 8, 1.0), 1.0, 0.0);
+        let agent = BettingAgent::new(&config, BasicStrategyAgent::new(&config, 10), policy, HiLo);
+        let stats = sim::simulate(&config, agent, 2000, 21);
+        assert!(stats.rounds > 0 && stats.rounds < 2000);
+        assert!(stats.wagered > 10 * stats.rounds as i64);
+
+        // Simulations with a real bankroll bet from the money the player has
+        let kelly = || {
+            let agent = BasicStrategyAgent::new(&config, 10);
+            BettingAgent::new(&config, agent, Kelly::new(1.0), HiLo)
+        };
+        let topped_up = sim::simulate(&config, kelly(), 2000, 21);
+        let real = sim::simulate_with_bankroll(&config, kelly(), 2000, 21, 2000);
+        let average_bet = |stats: &Statistics| stats.wagered as f64 / stats.rounds as f64;
+        assert!(average_bet(&real) < average_bet(&topped_up));
+        assert_eq!(topped_up.ruins, 0);
+
+        // Running out of money ends the simulation
+        let martingale = || {
+            let agent = BasicStrategyAgent::new(&config, 10);
+            BettingAgent::new(&config, agent, Martingale::new(10), HiLo)
+        };
+        let ruined = sim::simulate_with_bankroll(&config, martingale(), 5000, 21, 100);
+        assert_eq!(ruined.ruins, 1);
+        assert!(ruined.rounds < 5000);
+        // The player is left with less than the minimum bet
+        assert!((-100..=-91).contains(&ruined.net));
+        let broke = sim::simulate_with_bankroll(&config, martingale(), 5000, 21, 5);
+        assert_eq!((broke.rounds, broke.ruins), (0, 1));
+    }
+
+    #[test]
+    fn risk_tests() {
+        let close = |a: f64, b: f64, tolerance: f64| (a - b).abs() < tolerance;
+
+        // Analytic figures
+        let risk = RiskAnalysis::new(0.01, 1.3);
+        assert!(close(risk.n0(), 13000.0, 1e-6));
+        assert!(close(risk.score(), 1_000_000.0 / 13000.0, 1e-6));
+        assert!(close(risk.risk_of_ruin(100.0), (-2.0f64 / 1.3).exp(), 1e-9));
+        assert!(close(risk.hourly_win_rate(100.0), 1.0, 1e-9));
+        assert!(close(risk.hourly_std_dev(100.0), 130f64.sqrt(), 1e-9));
+        let within = risk.risk_of_ruin_within(100.0, 10_000);
+        assert!(within > risk.risk_of_ruin_within(100.0, 1000));
+        assert!(within < risk.risk_of_ruin(100.0));
+        assert!(risk.risk_of_ruin_within(100.0, 100_000_000) > risk.risk_of_ruin(100.0) - 1e-3);
+        let losing = RiskAnalysis::new(-0.005, 1.3);
+        assert_eq!(losing.risk_of_ruin(1000.0), 1.0);
+        assert_eq!(losing.n0(), f64::INFINITY);
+        assert_eq!(losing.score(), 0.0);
+        assert!(losing.risk_of_ruin_within(1000.0, 1_000_000) > 0.99);
+
+        // Mean and variance from recorded rounds
+        let nets: Vec<i64> = (0..1000).map(|i| if i < 505 { 1 } else { -1 }).collect();
+        let recorded = RiskAnalysis::from_nets(&nets);
+        assert!(close(recorded.mean, 0.01, 1e-9));
+        assert!(close(recorded.variance, 1.0, 1e-3));
+
+        // Bankroll trajectories agree with the analytic risk of ruin
+        let empirical = risk::empirical_risk_of_ruin(&nets, 20, 2000, 4000, 21);
+        assert!(close(
+            empirical,
+            recorded.risk_of_ruin_within(20.0, 2000),
+            0.03
+        ));
+        assert_eq!(
+            risk::empirical_risk_of_ruin(&nets, 20, 2000, 4000, 21),
+            empirical
+        );
+        assert_eq!(risk::empirical_risk_of_ruin(&nets, 5000, 100, 100, 21), 0.0);
+        assert_eq!(risk::empirical_risk_of_ruin(&[-10], 50, 5, 10, 21), 1.0);
+
+        // From a simulation or a stream of rounds
+        let config = game::DEFAULT_CONFIG;
+        let stats = sim::simulate(&config, BasicStrategyAgent::new(&config, 10), 500, 21);
+        let from_stats = RiskAnalysis::from(&stats);
+        assert!(close(from_stats.mean, stats.net as f64 / 500.0, 1e-9));
+        assert!(close(from_stats.std_dev(), stats.std_dev() * 10.0, 1e-6));
+
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let mut shoe = cards::create_shoe(6);
+        cards::shuffle_deck_seeded(&mut shoe, 21);
+        let mut dealer = Dealer::new(shoe, config, callback);
+        dealer.set_seed(21);
+        let agent = BasicStrategyAgent::new(&config, 10);
+        dealer
+            .players_mut()
+            .push(Player::with_agent(1_000_000, agent));
+        let rounds: Vec<game::RoundResult> = (0..200).map(|_| dealer.play_round(true)).collect();
+        let nets = risk::round_nets(&rounds, 0);
+        assert_eq!(nets.len(), 200);
+        assert_eq!(
+            nets.iter().sum::<i64>(),
+            (dealer.players()[0].money() - 1_000_000) as i64
+        );
+        assert!(risk::round_nets(&rounds, 1).is_empty());
+    }
+
+    #[test]
+    fn side_bet_tests() {
+        let settle = |side_bet: &dyn SideBet, hand: &str, up: &str| {
+            side_bet.settle_on_deal(&stacked(hand), stacked(up)[0])
+        };
+        let win = |odds| Some(SideBetOutcome::Win(odds));
+
+        // Perfect Pairs, with a custom paytable
+        let perfect_pairs = PerfectPairs {
+            perfect: 30,
+            ..PerfectPairs::new()
+        };
+        assert_eq!(settle(&perfect_pairs, "8H 8H", "2C"), win(30));
+        assert_eq!(settle(&perfect_pairs, "8H 8D", "2C"), win(12));
+        assert_eq!(settle(&perfect_pairs, "8H 8S", "2C"), win(6));
+        assert_eq!(
+            settle(&perfect_pairs, "8H 9H", "2C"),
+            Some(SideBetOutcome::Loss)
+        );
+
+        // 21+3
+        let twenty_one_plus_three = TwentyOnePlusThree::new();
+        assert_eq!(settle(&twenty_one_plus_three, "7S 7S", "7S"), win(100));
+        assert_eq!(settle(&twenty_one_plus_three, "2H 3H", "AH"), win(40));
+        assert_eq!(settle(&twenty_one_plus_three, "7S 7D", "7C"), win(30));
+        assert_eq!(settle(&twenty_one_plus_three, "QC KD", "AH"), win(10));
+        assert_eq!(settle(&twenty_one_plus_three, "2D 9D", "KD"), win(5));
+        assert_eq!(
+            settle(&twenty_one_plus_three, "KH AD", "2C"),
+            Some(SideBetOutcome::Loss)
+        );
+
+        // Lucky Ladies waits for the dealer's hand with a pair of queens of hearts
+        let lucky_ladies = LuckyLadies::new();
+        assert_eq!(settle(&lucky_ladies, "TS TS", "2C"), win(19));
+        assert_eq!(settle(&lucky_ladies, "KH TH", "2C"), win(9));
+        assert_eq!(settle(&lucky_ladies, "AH 9C", "2C"), win(4));
+        assert_eq!(
+            settle(&lucky_ladies, "TH 9H", "2C"),
+            Some(SideBetOutcome::Loss)
+        );
+        assert_eq!(settle(&lucky_ladies, "QH QH", "2C"), None);
+        let queens = stacked("QH QH");
+        assert_eq!(
+            lucky_ladies.settle_on_dealer_final(&queens, &stacked("2C TS 5D")),
+            SideBetOutcome::Win(125)
+        );
+        // Any other hand loses when settled with the dealer's hand
+        assert_eq!(
+            lucky_ladies.settle_on_dealer_final(&stacked("TS TS"), &stacked("AC KS")),
+            SideBetOutcome::Loss
+        );
+
+        // Hands with fewer than two cards lose rather than panic
+        let loss = Some(SideBetOutcome::Loss);
+        for side_bet in [
+            &perfect_pairs as &dyn SideBet,
+            &twenty_one_plus_three,
+            &lucky_ladies,
+        ]
+        .iter()
+        {
+            assert_eq!(settle(*side_bet, "", "2C"), loss);
+            assert_eq!(settle(*side_bet, "QH", "2C"), loss);
+        }
+        assert_eq!(
+            lucky_ladies.settle_on_dealer_final(&stacked("QH"), &stacked("AC KS")),
+            SideBetOutcome::Loss
+        );
+
+        // Buster pays by the amount of cards in a busted dealer hand
+        let buster = Buster::new();
+        let hand = stacked("TH 7D");
+        assert_eq!(settle(&buster, "TH 7D", "2C"), None);
+        let bust = |dealer: &str| buster.settle_on_dealer_final(&hand, &stacked(dealer));
+        assert_eq!(bust("TS 6D 8C"), SideBetOutcome::Win(1));
+        assert_eq!(bust("2S 2D 2C 2H 3S 3D 3C TS"), SideBetOutcome::Win(250));
+        assert_eq!(bust("2S 2D 2C 2H 3S 3D 3C 2S TS"), SideBetOutcome::Win(250));
+        assert_eq!(bust("TS 7D"), SideBetOutcome::Loss);
+        assert_eq!(SideBetOutcome::Win(12).net(5), 60);
+        assert_eq!(SideBetOutcome::Loss.net(5), -5);
+
+        // Side bets are offered in order after the player bets
+        let callback = |_: DealerRequest, _: Option<&Player>, _: &Dealer| PlayerAction::None;
+        let pair = |cards: &str| {
+            let cards = stacked(cards);
+            [cards[0], cards[1]]
+        };
+        let mut dealer = Dealer::new(Shoe::new(6), game::DEFAULT_CONFIG, callback);
+        dealer.add_side_bet(PerfectPairs::new());
+        dealer.add_side_bet(TwentyOnePlusThree::new());
+        dealer.add_side_bet(LuckyLadies::new());
+        dealer.add_side_bet(Buster::new());
+        dealer.add_side_bet(PerfectPairs::new());
+        assert_eq!(dealer.side_bets().len(), 4);
+        dealer.players_mut().push(Player::new(1000));
+        dealer.rig(&[pair("8D 8H")], pair("6S TH"), &stacked("6C"));
+        dealer.start_round(true);
+        dealer.apply(PlayerAction::Bet(10)).unwrap();
+        assert_eq!(
+            dealer.pending(),
+            Some(Decision::SideBet {
+                player: 0,
+                kind: SideBetKind::PerfectPairs
+            })
+        );
+        let buster_bet = PlayerAction::SideBet(SideBetKind::Buster, 5);
+        assert_eq!(
+            dealer.apply(buster_bet),
+            Err(PlayerActionError::UnexpectedAction(0, buster_bet))
+        );
+        let nothing = PlayerAction::SideBet(SideBetKind::PerfectPairs, 0);
+        assert_eq!(
+            dealer.apply(nothing),
+            Err(PlayerActionError::UnexpectedAction(0, nothing))
+        );
+        let too_much = PlayerAction::SideBet(SideBetKind::PerfectPairs, 5000);
+        assert_eq!(
+            dealer.apply(too_much),
+            Err(PlayerActionError::NotEnoughMoney(0, too_much))
+        );
+        for kind in [
+            SideBetKind::PerfectPairs,
+            SideBetKind::TwentyOnePlusThree,
+            SideBetKind::LuckyLadies,
+            SideBetKind::Buster,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                dealer.pending().unwrap().request(),
+                DealerRequest::SideBet(*kind)
+            );
+            dealer.apply(PlayerAction::SideBet(*kind, 5)).unwrap();
+        }
+        // Perfect Pairs is paid on the deal
+        assert_eq!(*dealer.players()[0].money(), 1000 - 30 + 65);
+        dealer.apply(PlayerAction::Stand).unwrap();
+        let result = &dealer.result().unwrap().players[0];
+        let side_bet = |kind, outcome, net| SideBetResult {
+            kind,
+            outcome,
+            wagered: 5,
+            net,
+        };
+        assert_eq!(
+            result.side_bets,
+            vec![
+                side_bet(SideBetKind::PerfectPairs, SideBetOutcome::Win(12), 60),
+                side_bet(SideBetKind::TwentyOnePlusThree, SideBetOutcome::Loss, -5),
+                side_bet(SideBetKind::LuckyLadies, SideBetOutcome::Loss, -5),
+                side_bet(SideBetKind::Buster, SideBetOutcome::Win(1), 5),
+            ]
+        );
+        assert_eq!(result.net(), 65);
+        assert_eq!(*dealer.players()[0].money(), 1065);
+
+        // Agents are asked for side bets, and decline by default
+        let mut dealer = Dealer::new(Shoe::new(6), game::DEFAULT_CONFIG, callback);
+        dealer.add_side_bet(LuckyLadies::new());
+        dealer.add_side_bet(Buster::new());
+        let agent = BasicStrategyAgent::new(&game::DEFAULT_CONFIG, 10);
+        dealer.players_mut().push(Player::with_agent(1000, agent));
+        let ladies = |request: DealerRequest, _: Option<&Player>, _: &Dealer| match request {
+            DealerRequest::Bet => PlayerAction::Bet(10),
+            DealerRequest::SideBet(SideBetKind::LuckyLadies) => {
+                PlayerAction::SideBet(SideBetKind::LuckyLadies, 10)
+            }
+            DealerRequest::SideBet(kind) => PlayerAction::SideBet(kind, 5),
+            _ => PlayerAction::None,
+        };
+        dealer.players_mut().push(Player::with_agent(1000, ladies));
+        dealer.rig(&[pair("TD 9C"), pair("QH QH")], pair("AS KD"), &[]);
+        let result = dealer.play_round(true);
+        assert!(result.players[0].side_bets.is_empty());
+        assert_eq!(
+            result.players[1].side_bets,
+            vec![
+                SideBetResult {
+                    kind: SideBetKind::LuckyLadies,
+                    outcome: SideBetOutcome::Win(1000),
+                    wagered: 10,
+                    net: 10_000,
+                },
+                SideBetResult {
+                    kind: SideBetKind::Buster,
+                    outcome: SideBetOutcome::Loss,
+                    wagered: 5,
+                    net: -5,
+                },
+            ]
+        );
+        assert_eq!(result.players[1].net(), 10_000 - 5 - 10);
+        assert_eq!(*dealer.players()[1].money(), 1000 + 10_000 - 5 - 10);
+    }
 }